@@ -1,8 +1,12 @@
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
+use std::sync::mpsc;
+use std::{process, thread};
 
 use clap::Parser;
 use keisteen::server::conn::ConnectionManager;
 use keisteen::server::folder::ServerFolder;
+use keisteen::server::tick::TickLoop;
 use keisteen::server::{Server, ServerHandle};
 
 #[derive(Parser)]
@@ -26,5 +30,31 @@ fn main() {
     let server = Server::new(server_folder);
     let handle = ServerHandle::new(server);
 
-    ConnectionManager::new(handle).bind(address).expect("should start server");
+    let (packet_sender, packet_receiver) = mpsc::channel();
+    let mut connection_manager =
+        ConnectionManager::new(handle.clone(), packet_sender).expect("should create network");
+    connection_manager.bind(address).expect("should start server");
+
+    let tick_loop = TickLoop::new(handle, connection_manager.network().clone(), packet_receiver);
+    let watchdog = tick_loop.watchdog().cloned();
+
+    thread::Builder::new()
+        .name("network".to_string())
+        .spawn(move || {
+            if let Some(watchdog) = watchdog {
+                watchdog.watch_current_thread();
+            }
+
+            // Without the network thread the server can't accept or serve any players, so the
+            // whole process is stopped instead of leaving the tick loop running on its own.
+            match panic::catch_unwind(AssertUnwindSafe(|| connection_manager.run())) {
+                Ok(Ok(())) => log::error!("network thread stopped"),
+                Ok(Err(err)) => log::error!("network thread stopped: {err}"),
+                Err(_) => log::error!("network thread panicked"),
+            }
+            process::exit(1);
+        })
+        .expect("should spawn network thread");
+
+    tick_loop.run();
 }
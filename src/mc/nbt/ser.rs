@@ -211,6 +211,8 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
             NbtTag::List { payload, type_id, .. } => {
                 let mut serializer = Serializer::new();
                 value.serialize(&mut serializer)?;
+                // List elements are never named.
+                serializer.output.set_name(None);
                 if *type_id == nbt::value::TAG_END {
                     *type_id = serializer.output.tag_type_id();
                 }
@@ -2,16 +2,19 @@ use crate::mc::protocol::packet::PacketData;
 use crate::mc::protocol::packet::client::ClientboundPacket;
 use crate::mc::protocol::packet::known_pack::KnownPack;
 use crate::mc::protocol::packet::registry_data_entry::RegistryDataEntry;
-use crate::mc::types::Identifier;
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::{Identifier, VarInt};
 
 #[derive(Debug)]
-pub struct CookieRequest;
+pub struct CookieRequest {
+    pub key: Identifier,
+}
 
 impl ClientboundPacket for CookieRequest {
     const PACKET_ID: i32 = 0x00;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.key);
     }
 }
 
@@ -31,13 +34,15 @@ impl ClientboundPacket for PluginMessage {
 }
 
 #[derive(Debug)]
-pub struct Disconnected;
+pub struct Disconnected {
+    pub reason: TextComponent,
+}
 
 impl ClientboundPacket for Disconnected {
     const PACKET_ID: i32 = 0x02;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.reason);
     }
 }
 
@@ -51,13 +56,15 @@ impl ClientboundPacket for FinishConfig {
 }
 
 #[derive(Debug)]
-pub struct KeepAlive;
+pub struct KeepAlive {
+    pub id: i64,
+}
 
 impl ClientboundPacket for KeepAlive {
     const PACKET_ID: i32 = 0x04;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.id);
     }
 }
 
@@ -121,24 +128,32 @@ impl ClientboundPacket for AddResourcePack {
 }
 
 #[derive(Debug)]
-pub struct StoreCookie;
+pub struct StoreCookie {
+    pub key: Identifier,
+    pub payload: Vec<u8>,
+}
 
 impl ClientboundPacket for StoreCookie {
     const PACKET_ID: i32 = 0x0A;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.key);
+        data.write_prefixed(self.payload);
     }
 }
 
 #[derive(Debug)]
-pub struct Transfer;
+pub struct Transfer {
+    pub host: String,
+    pub port: VarInt,
+}
 
 impl ClientboundPacket for Transfer {
     const PACKET_ID: i32 = 0x0B;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.host);
+        data.write(self.port);
     }
 }
 
@@ -2,16 +2,21 @@ use uuid::Uuid;
 
 use crate::mc::protocol::packet::PacketData;
 use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::text::text_component::TextComponent;
 use crate::mc::types::{Identifier, VarInt};
+use crate::server::player_profile::ProfileProperty;
 
 #[derive(Debug)]
-pub struct Disconnected;
+pub struct Disconnected {
+    pub reason: TextComponent,
+}
 
 impl ClientboundPacket for Disconnected {
     const PACKET_ID: i32 = 0x00;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        let reason = serde_json::to_string(&self.reason).expect("should serialize reason");
+        data.write(reason);
     }
 }
 
@@ -38,7 +43,7 @@ impl ClientboundPacket for EncryptionRequest {
 pub struct LoginSuccess {
     pub uuid: Uuid,
     pub username: String,
-    pub properties: (),
+    pub properties: Vec<ProfileProperty>,
 }
 
 impl ClientboundPacket for LoginSuccess {
@@ -47,7 +52,7 @@ impl ClientboundPacket for LoginSuccess {
     fn encode(self, data: &mut PacketData) {
         data.write(self.uuid);
         data.write(self.username);
-        data.write_prefixed(Vec::<()>::new()); // TODO: Write properties.
+        data.write_prefixed(self.properties);
     }
 }
 
@@ -74,8 +79,10 @@ pub struct LoginPluginRequest {
 impl ClientboundPacket for LoginPluginRequest {
     const PACKET_ID: i32 = 0x04;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.message_id);
+        data.write(self.channel);
+        data.write(self.data);
     }
 }
 
@@ -87,7 +94,7 @@ pub struct CookieRequest {
 impl ClientboundPacket for CookieRequest {
     const PACKET_ID: i32 = 0x05;
 
-    fn encode(self, _data: &mut PacketData) {
-        todo!()
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.key);
     }
 }
@@ -1,7 +1,62 @@
 use crate::mc::protocol::packet::PacketData;
 use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::text::text_component::TextComponent;
 use crate::mc::types::{Identifier, Position, VarInt};
 
+#[derive(Debug)]
+pub struct CookieRequest {
+    pub key: Identifier,
+}
+
+impl ClientboundPacket for CookieRequest {
+    const PACKET_ID: i32 = 0x15;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.key);
+    }
+}
+
+#[derive(Debug)]
+pub struct PluginMessage {
+    pub channel: Identifier,
+    pub data: Vec<u8>,
+}
+
+impl ClientboundPacket for PluginMessage {
+    const PACKET_ID: i32 = 0x18;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.channel);
+        data.write(self.data);
+    }
+}
+
+#[derive(Debug)]
+pub struct Disconnect {
+    pub reason: TextComponent,
+}
+
+impl ClientboundPacket for Disconnect {
+    const PACKET_ID: i32 = 0x1C;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.reason);
+    }
+}
+
+#[derive(Debug)]
+pub struct KeepAlive {
+    pub id: i64,
+}
+
+impl ClientboundPacket for KeepAlive {
+    const PACKET_ID: i32 = 0x26;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.id);
+    }
+}
+
 #[derive(Debug)]
 pub struct Login {
     pub entity_id: i32,
@@ -60,3 +115,33 @@ impl ClientboundPacket for Login {
         data.write(self.enforces_secure_chat);
     }
 }
+
+#[derive(Debug)]
+pub struct StoreCookie {
+    pub key: Identifier,
+    pub payload: Vec<u8>,
+}
+
+impl ClientboundPacket for StoreCookie {
+    const PACKET_ID: i32 = 0x71;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.key);
+        data.write_prefixed(self.payload);
+    }
+}
+
+#[derive(Debug)]
+pub struct Transfer {
+    pub host: String,
+    pub port: VarInt,
+}
+
+impl ClientboundPacket for Transfer {
+    const PACKET_ID: i32 = 0x7A;
+
+    fn encode(self, data: &mut PacketData) {
+        data.write(self.host);
+        data.write(self.port);
+    }
+}
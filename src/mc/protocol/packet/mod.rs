@@ -6,6 +6,7 @@ use uuid::Uuid;
 
 use crate::error::KeisteenResult;
 use crate::mc::nbt;
+use crate::mc::text::text_component::TextComponent;
 use crate::mc::types::{Identifier, Position, VarInt};
 
 pub mod client;
@@ -21,6 +22,12 @@ pub struct RawPacket {
 }
 
 impl RawPacket {
+    pub fn from_clientbound<P: client::ClientboundPacket>(packet: P) -> Self {
+        let mut data = PacketData::new();
+        packet.encode(&mut data);
+        Self { packet_id: VarInt::new(P::PACKET_ID), data }
+    }
+
     pub fn length(&self) -> VarInt {
         let length = self.packet_id.len() + self.data.bytes().len();
         VarInt::new(length as i32)
@@ -177,6 +184,13 @@ impl ProtocolWrite for nbt::NbtTag {
     }
 }
 
+impl ProtocolWrite for TextComponent {
+    fn write<W: io::Write>(&self, writer: &mut W) -> KeisteenResult<()> {
+        let value = nbt::to_value(self).wrap_err("failed to serialize text component")?;
+        value.write(writer).wrap_err("failed to write text component")
+    }
+}
+
 impl ProtocolWrite for Position {
     fn write<W: io::Write>(&self, writer: &mut W) -> KeisteenResult<()> {
         i64::from(*self).write(writer).wrap_err("failed to write position")
@@ -9,8 +9,11 @@ use crate::mc::protocol::packet::registry_data_entry::RegistryDataEntry;
 use crate::mc::protocol::packet::server::ServerboundPacket;
 use crate::mc::protocol::packet::{RawPacket, client};
 use crate::mc::protocol::registry::Registry;
+use crate::mc::text::ansi::AnsiRenderer;
 use crate::mc::types::{Identifier, VarInt};
-use crate::server::conn::{Connection, ConnectionState};
+use crate::server::client_settings::ClientSettings;
+use crate::server::conn::{Connection, ConnectionState, cookie, plugin_message};
+use crate::server::keep_alive;
 use crate::server::player::Player;
 
 pub fn handle_raw_packet(raw: RawPacket, conn: &mut Connection) -> KeisteenResult<()> {
@@ -30,52 +33,37 @@ pub fn handle_raw_packet(raw: RawPacket, conn: &mut Connection) -> KeisteenResul
 
 #[derive(Debug)]
 pub struct ClientInformation {
-    pub locale: String,
-    pub view_distance: i8,
-    pub chat_mode: VarInt,
-    pub chat_colors: bool,
-    pub displayed_skin_parts: u8,
-    pub main_hand: VarInt,
-    pub enable_text_filtering: bool,
-    pub allow_server_listing: bool,
-    pub particle_status: VarInt,
+    pub settings: ClientSettings,
 }
 
 impl ServerboundPacket for ClientInformation {
     const PACKET_ID: i32 = 0x00;
 
     fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
-        Ok(Self {
-            locale: raw.data.read()?,
-            view_distance: raw.data.read()?,
-            chat_mode: raw.data.read()?,
-            chat_colors: raw.data.read()?,
-            displayed_skin_parts: raw.data.read()?,
-            main_hand: raw.data.read()?,
-            enable_text_filtering: raw.data.read()?,
-            allow_server_listing: raw.data.read()?,
-            particle_status: raw.data.read()?,
-        })
+        Ok(Self { settings: raw.data.read()? })
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        // TODO: Do something with client information.
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.client_settings = self.settings.clone();
         Ok(())
     }
 }
 
 #[derive(Debug)]
-pub struct CookieResponse;
+pub struct CookieResponse {
+    pub key: Identifier,
+    pub payload: Option<Vec<u8>>,
+}
 
 impl ServerboundPacket for CookieResponse {
     const PACKET_ID: i32 = 0x01;
 
-    fn decode(_raw: RawPacket) -> KeisteenResult<Self> {
-        todo!()
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { key: raw.data.read()?, payload: cookie::read_cookie_payload(&mut raw.data)? })
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        todo!()
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_cookie(self.key.clone(), self.payload.clone())
     }
 }
 
@@ -90,19 +78,11 @@ impl ServerboundPacket for PluginMessage {
 
     fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
         let channel = raw.data.read()?;
-        let data_len = raw.data.bytes().len();
-        Ok(Self { channel, data: raw.data.read_predefined(data_len)? })
+        Ok(Self { channel, data: plugin_message::read_plugin_message_data(&mut raw.data)? })
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        if self.channel.namespace() == "minecraft" && self.channel.value() == "brand" {
-            let brand_string = str::from_utf8(&self.data)?;
-            log::debug!("client brand: \"{}\"", brand_string);
-        } else {
-            log::debug!("received channel message on channel '{}': {:?}", self.channel, self.data);
-        }
-
-        Ok(())
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_plugin_message(self.channel.clone(), self.data.clone())
     }
 }
 
@@ -126,12 +106,14 @@ impl ServerboundPacket for AcknowledgeFinishConfig {
             .server()
             .read(|server| server.player_list().can_player_login(player_profile.uuid()));
         if let Err(reason) = can_log_in {
-            log::error!("Player could not log in: {reason}");
+            log::error!("Player could not log in: {}", AnsiRenderer::console().render(&reason));
             conn.disconnect(Some(reason));
             return Ok(());
         }
 
-        let player = Player::new(player_profile);
+        let keep_alive = std::mem::replace(&mut conn.keep_alive, keep_alive::KeepAlive::new());
+        let player =
+            Player::new(player_profile, conn.id(), conn.client_settings().clone(), keep_alive);
         let player_entity_id = player.entity_id();
         let max_players = conn.server().read(|server| server.player_list().max_players());
 
@@ -180,17 +162,23 @@ impl ServerboundPacket for AcknowledgeFinishConfig {
 }
 
 #[derive(Debug)]
-pub struct KeepAlive;
+pub struct KeepAlive {
+    pub id: i64,
+}
 
 impl ServerboundPacket for KeepAlive {
     const PACKET_ID: i32 = 0x04;
 
-    fn decode(_raw: RawPacket) -> KeisteenResult<Self> {
-        todo!()
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { id: raw.data.read()? })
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        todo!()
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        if !conn.keep_alive.receive(self.id) {
+            conn.disconnect(Some(keep_alive::KeepAlive::timeout_reason()));
+        }
+
+        Ok(())
     }
 }
 
@@ -1,8 +1,10 @@
 use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::RawPacket;
 use crate::mc::protocol::packet::server::ServerboundPacket;
+use crate::mc::text::text_component::TextComponent;
 use crate::mc::types::VarInt;
 use crate::server::conn::{Connection, ConnectionState};
+use crate::server::folder::ForwardingMode;
 
 use eyre::bail;
 
@@ -18,7 +20,14 @@ pub struct Handshake {
     pub protocol_version: VarInt,
     pub server_address: String,
     pub server_port: u16,
-    pub intent: ConnectionState,
+    pub intent: HandshakeIntent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeIntent {
+    Status,
+    Login,
+    Transfer,
 }
 
 impl ServerboundPacket for Handshake {
@@ -30,15 +39,32 @@ impl ServerboundPacket for Handshake {
             server_address: raw.data.read()?,
             server_port: raw.data.read()?,
             intent: match raw.data.read::<VarInt>()?.raw() {
-                1 => ConnectionState::Status,
-                2 => ConnectionState::Login,
-                3 => ConnectionState::Transfer,
-                _ => unreachable!("invalid intent value"),
+                1 => HandshakeIntent::Status,
+                2 => HandshakeIntent::Login,
+                3 => HandshakeIntent::Transfer,
+                intent => bail!("invalid handshake intent: {intent}"),
             },
         })
     }
 
     fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        // Transferred clients log in like any other client, they only differ in their intent.
+        conn.state = match self.intent {
+            HandshakeIntent::Status => ConnectionState::Status,
+            HandshakeIntent::Login | HandshakeIntent::Transfer => ConnectionState::Login,
+        };
+        conn.is_transferred = self.intent == HandshakeIntent::Transfer;
+
+        if let ConnectionState::Status = conn.state
+            && !conn
+                .server()
+                .read(|server| server.server_folder().config().properties().enable_status)
+        {
+            // Like vanilla, the connection is closed without answering the status request.
+            conn.disconnect(None);
+            return Ok(());
+        }
+
         if self.protocol_version != crate::MC_PROTOCOL {
             log::warn!(
                 "client has protocol version {}, but server is {}",
@@ -46,10 +72,52 @@ impl ServerboundPacket for Handshake {
                 crate::MC_PROTOCOL
             );
 
-            // TODO: Kick player.
+            // Status requests are still answered, so the client can show the server's version.
+            if let ConnectionState::Login = conn.state {
+                self.kick_incompatible_client(conn)?;
+            }
+
+            return Ok(());
+        }
+
+        if conn.is_transferred {
+            let accepts_transfers = conn
+                .server()
+                .read(|server| server.server_folder().config().properties().accepts_transfers);
+            if !accepts_transfers {
+                log::info!("rejecting transfer from {}", conn.remote_address());
+
+                let reason = TextComponent::translatable(
+                    "multiplayer.disconnect.transfers_disabled",
+                    vec![],
+                );
+                conn.disconnect(Some(reason));
+                return Ok(());
+            }
+        }
+
+        if let ConnectionState::Login = conn.state {
+            let forwarding_mode =
+                conn.server().read(|server| server.server_folder().config().forwarding_mode());
+            if forwarding_mode == ForwardingMode::BungeeCord {
+                conn.accept_bungeecord_forwarding(&self.server_address)?;
+            }
         }
 
-        conn.state = self.intent;
+        Ok(())
+    }
+}
+
+impl Handshake {
+    fn kick_incompatible_client(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        let key = if self.protocol_version < crate::MC_PROTOCOL {
+            "multiplayer.disconnect.outdated_client"
+        } else {
+            "multiplayer.disconnect.outdated_server"
+        };
+        let reason = TextComponent::translatable(key, vec![TextComponent::text(crate::MC_VERSION)]);
+
+        conn.disconnect(Some(reason));
 
         Ok(())
     }
@@ -1,13 +1,21 @@
+use std::thread;
+
 use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::known_pack::KnownPack;
 use crate::mc::protocol::packet::server::ServerboundPacket;
 use crate::mc::protocol::packet::{self, ProtocolWrite, RawPacket, client};
+use crate::mc::text::text_component::TextComponent;
 use crate::mc::types::{Identifier, VarInt};
-use crate::server::conn::{Connection, ConnectionState};
+use crate::server::conn::{Connection, ConnectionState, LoginState, cookie, login_query};
+use crate::server::folder::ForwardingMode;
 use crate::server::player_profile::PlayerProfile;
+
 use eyre::{Context, bail};
 use uuid::Uuid;
 
+/// The length of the AES key the client generates for encryption.
+const SHARED_SECRET_LENGTH: usize = 16;
+
 pub fn handle_raw_packet(raw: RawPacket, conn: &mut Connection) -> KeisteenResult<()> {
     match raw.packet_id.raw() {
         CookieResponse::PACKET_ID => CookieResponse::decode(raw)?.handle(conn),
@@ -33,17 +41,44 @@ impl ServerboundPacket for Start {
     }
 
     fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        if conn.login_state != LoginState::AwaitingStart {
+            bail!("client sent login start in {:?} login state", conn.login_state);
+        }
+
         log::info!("{} ({}) wants to log in", self.name, self.player_uuid);
 
         conn.player_profile = Some(PlayerProfile::new(self.player_uuid, self.name.clone()));
 
-        // TODO: Implement authentication with Mojang's servers.
-        let should_authenticate = false;
-        let packet = conn.server().read(|server| {
-            server.crypt_keys().generate_encryption_request_packet(should_authenticate)
+        let (online_mode, forwarding_mode) = conn.server().read(|server| {
+            let config = server.server_folder().config();
+            (config.properties().online_mode, config.forwarding_mode())
         });
 
+        // Players behind a proxy have already been authenticated by the proxy.
+        match forwarding_mode {
+            ForwardingMode::None => {}
+            ForwardingMode::BungeeCord => {
+                if !conn.complete_bungeecord_forwarding(self.name.clone()) {
+                    bail!("client started login without forwarded player information");
+                }
+                return finish_login(conn);
+            }
+            ForwardingMode::Velocity => {
+                conn.request_velocity_forwarding()?;
+                return finish_login(conn);
+            }
+        }
+
+        if !online_mode {
+            return finish_login(conn);
+        }
+
+        let packet = conn
+            .server()
+            .read(|server| server.crypt_keys().generate_encryption_request_packet(true));
+
         conn.send_packet(packet)?;
+        conn.login_state = LoginState::AwaitingEncryption;
 
         Ok(())
     }
@@ -66,42 +101,120 @@ impl ServerboundPacket for EncryptionResponse {
     }
 
     fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
-        conn.server().read(|server| {
-            if !server.crypt_keys().verify_token(&self.verify_token).expect("should verify token") {
+        if conn.login_state != LoginState::AwaitingEncryption {
+            bail!("client sent an encryption response in {:?} login state", conn.login_state);
+        }
+        conn.login_state = LoginState::AwaitingAuthentication;
+
+        let shared_secret = conn.server().read(|server| {
+            let crypt_keys = server.crypt_keys();
+            let is_verified = crypt_keys
+                .verify_token(&self.verify_token)
+                .wrap_err("failed to decrypt verification token")?;
+            if !is_verified {
                 bail!("verification tokens are not the same");
             }
 
-            Ok(())
+            crypt_keys.decrypt(&self.shared_secret).wrap_err("failed to decrypt shared secret")
         })?;
 
-        conn.enable_encryption(&self.shared_secret).wrap_err("failed to enable encryption")?;
-        conn.enable_compression().wrap_err("failed to enable compression")?;
+        if shared_secret.len() != SHARED_SECRET_LENGTH {
+            bail!(
+                "shared secret should be {SHARED_SECRET_LENGTH} bytes, got {}",
+                shared_secret.len()
+            );
+        }
 
-        let player_profile = conn.player_profile();
-        conn.send_packet(packet::client::login::LoginSuccess {
-            uuid: player_profile.uuid().clone(),
-            username: player_profile.username().to_string(),
-            properties: player_profile.properties().clone(),
-        })?;
+        conn.enable_encryption(&shared_secret).wrap_err("failed to enable encryption")?;
+
+        let (server_hash, session_service) = conn.server().read(|server| {
+            (server.crypt_keys().server_hash(&shared_secret), server.session_service())
+        });
+
+        // Authentication is a blocking HTTP request, so it should not hold up the network thread.
+        let username = conn.player_profile().username().to_string();
+        let handle = conn.handle();
+        thread::spawn(move || {
+            let result = session_service.has_joined(&username, &server_hash);
+            handle.execute(move |conn| complete_authentication(conn, &username, result));
+        });
 
         Ok(())
     }
 }
 
+fn complete_authentication(
+    conn: &mut Connection,
+    username: &str,
+    result: KeisteenResult<Option<PlayerProfile>>,
+) -> KeisteenResult<()> {
+    // The client may have been disconnected or sent other packets in the meantime.
+    if conn.login_state != LoginState::AwaitingAuthentication {
+        return Ok(());
+    }
+
+    match result {
+        Ok(Some(profile)) => {
+            log::info!("authenticated {} ({})", profile.username(), profile.uuid());
+            conn.player_profile = Some(profile);
+        }
+        Ok(None) => {
+            conn.disconnect(Some(TextComponent::translatable(
+                "multiplayer.disconnect.unverified_username",
+                Vec::new(),
+            )));
+            return Ok(());
+        }
+        Err(err) => {
+            log::error!("failed to authenticate {username}: {err:?}");
+            conn.disconnect(Some(TextComponent::translatable(
+                "multiplayer.disconnect.authservers_down",
+                Vec::new(),
+            )));
+            return Ok(());
+        }
+    }
+
+    finish_login(conn)
+}
+
+fn finish_login(conn: &mut Connection) -> KeisteenResult<()> {
+    conn.login_state = LoginState::AwaitingQueries;
+    conn.after_login_queries(send_login_success)
+}
+
+fn send_login_success(conn: &mut Connection) -> KeisteenResult<()> {
+    conn.login_state = LoginState::SuccessSent;
+    conn.enable_compression().wrap_err("failed to enable compression")?;
+
+    let player_profile = conn.player_profile();
+    conn.send_packet(packet::client::login::LoginSuccess {
+        uuid: player_profile.uuid(),
+        username: player_profile.username().to_string(),
+        properties: player_profile.properties().to_vec(),
+    })?;
+
+    Ok(())
+}
+
+#[derive(Debug)]
 pub struct PluginResponse {
     pub message_id: VarInt,
-    pub data: Vec<u8>,
+    pub data: Option<Vec<u8>>,
 }
 
 impl ServerboundPacket for PluginResponse {
     const PACKET_ID: i32 = 0x02;
 
-    fn decode(_raw: RawPacket) -> KeisteenResult<Self> {
-        todo!()
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self {
+            message_id: raw.data.read()?,
+            data: login_query::read_login_plugin_response_data(&mut raw.data)?,
+        })
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        todo!()
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_login_plugin_response(self.message_id.raw(), self.data.clone())
     }
 }
 
@@ -116,11 +229,16 @@ impl ServerboundPacket for LoginAcknowledged {
     }
 
     fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        if conn.login_state != LoginState::SuccessSent {
+            bail!("client acknowledged login in {:?} login state", conn.login_state);
+        }
+
         log::debug!("login acknowledged");
 
         conn.state = ConnectionState::Config;
 
         self.send_brand_plugin_message_packet(crate::BRAND, conn)?;
+        conn.announce_channels()?;
 
         // TODO: Send Feature Flags
 
@@ -164,17 +282,80 @@ impl LoginAcknowledged {
 #[derive(Debug)]
 pub struct CookieResponse {
     pub key: Identifier,
-    pub payload: Vec<u8>,
+    pub payload: Option<Vec<u8>>,
 }
 
 impl ServerboundPacket for CookieResponse {
     const PACKET_ID: i32 = 0x04;
 
-    fn decode(_raw: RawPacket) -> KeisteenResult<Self> {
-        todo!()
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { key: raw.data.read()?, payload: cookie::read_cookie_payload(&mut raw.data)? })
+    }
+
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_cookie(self.key.clone(), self.payload.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONLINE: &str = "";
+    const OFFLINE: &str = "[properties]\nonline_mode = false";
+
+    fn logging_in(config: &str) -> (Connection, std::net::TcpStream) {
+        let (mut conn, client) = Connection::for_test(config);
+        conn.state = ConnectionState::Login;
+        (conn, client)
     }
 
-    fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()> {
-        todo!()
+    fn start() -> Start {
+        Start { name: "Steve".to_string(), player_uuid: Uuid::new_v4() }
+    }
+
+    #[test]
+    fn rejects_login_acknowledged_before_authentication() {
+        let (mut conn, _client) = logging_in(ONLINE);
+        start().handle(&mut conn).unwrap();
+        assert_eq!(conn.login_state, LoginState::AwaitingEncryption);
+
+        assert!(LoginAcknowledged.handle(&mut conn).is_err());
+        assert!(matches!(conn.state, ConnectionState::Login));
+    }
+
+    #[test]
+    fn rejects_login_packets_out_of_order() {
+        let (mut conn, _client) = logging_in(ONLINE);
+        assert!(LoginAcknowledged.handle(&mut conn).is_err());
+
+        let response =
+            EncryptionResponse { shared_secret: vec![0; 128], verify_token: vec![0; 128] };
+        assert!(response.handle(&mut conn).is_err());
+
+        start().handle(&mut conn).unwrap();
+        assert!(start().handle(&mut conn).is_err());
+    }
+
+    #[test]
+    fn ignores_authentication_once_login_has_moved_on() {
+        let (mut conn, _client) = logging_in(ONLINE);
+        start().handle(&mut conn).unwrap();
+
+        let impostor = PlayerProfile::new(Uuid::new_v4(), "Alex".to_string());
+        complete_authentication(&mut conn, "Steve", Ok(Some(impostor))).unwrap();
+
+        assert_eq!(conn.player_profile().username(), "Steve");
+        assert_eq!(conn.login_state, LoginState::AwaitingEncryption);
+    }
+
+    #[test]
+    fn acknowledges_login_after_login_success() {
+        let (mut conn, _client) = logging_in(OFFLINE);
+        start().handle(&mut conn).unwrap();
+        assert_eq!(conn.login_state, LoginState::SuccessSent);
+
+        LoginAcknowledged.handle(&mut conn).unwrap();
+        assert!(matches!(conn.state, ConnectionState::Config));
     }
 }
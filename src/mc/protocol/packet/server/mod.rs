@@ -1,6 +1,7 @@
 use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::RawPacket;
-use crate::server::conn::Connection;
+use crate::server::Server;
+use crate::server::conn::{Connection, ConnectionId};
 
 pub mod config;
 pub mod handshake;
@@ -17,3 +18,14 @@ pub trait ServerboundPacket {
 
     fn handle(&self, _conn: &mut Connection) -> KeisteenResult<()>;
 }
+
+/// A packet received in the play state, which is handled on the server thread.
+pub trait ServerboundPlayPacket {
+    const PACKET_ID: i32 = 0x00;
+
+    fn decode(raw: RawPacket) -> KeisteenResult<Self>
+    where
+        Self: Sized;
+
+    fn handle(&self, server: &mut Server, connection: ConnectionId) -> KeisteenResult<()>;
+}
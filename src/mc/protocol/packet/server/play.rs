@@ -1,9 +1,120 @@
 use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::RawPacket;
-use crate::server::conn::Connection;
+use crate::mc::protocol::packet::server::{ServerboundPacket, ServerboundPlayPacket};
+use crate::mc::types::Identifier;
+use crate::server::client_settings::ClientSettings;
+use crate::server::conn::{Connection, ConnectionId, cookie, plugin_message};
+use crate::server::{Server, keep_alive};
 
-pub fn handle_raw_packet(raw: RawPacket, _conn: &mut Connection) -> KeisteenResult<()> {
+/// Handles a play packet on the network thread. Packets that concern the connection itself are
+/// handled right away, all others are queued for the server thread.
+pub fn handle_raw_packet(raw: RawPacket, conn: &mut Connection) -> KeisteenResult<()> {
     match raw.packet_id.raw() {
+        ClientInformation::PACKET_ID => ClientInformation::decode(raw)?.handle(conn),
+        CookieResponse::PACKET_ID => CookieResponse::decode(raw)?.handle(conn),
+        PluginMessage::PACKET_ID => PluginMessage::decode(raw)?.handle(conn),
+        _ => conn.queue_play_packet(raw),
+    }
+}
+
+/// Handles a play packet on the server thread, during the tick after it was received.
+pub fn handle_queued_packet(
+    raw: RawPacket,
+    server: &mut Server,
+    connection: ConnectionId,
+) -> KeisteenResult<()> {
+    match raw.packet_id.raw() {
+        KeepAlive::PACKET_ID => KeepAlive::decode(raw)?.handle(server, connection),
         _ => Ok(()),
     }
 }
+
+#[derive(Debug)]
+pub struct ClientInformation {
+    pub settings: ClientSettings,
+}
+
+impl ServerboundPacket for ClientInformation {
+    const PACKET_ID: i32 = 0x0D;
+
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { settings: raw.data.read()? })
+    }
+
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.client_settings = self.settings.clone();
+
+        // The player is updated right away as well, so both agree on e.g. the locale.
+        let connection = conn.id();
+        conn.server().update(|server| {
+            if let Some(player) = server.player_list_mut().get_player_by_connection_mut(connection)
+            {
+                player.set_client_settings(self.settings.clone());
+            }
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CookieResponse {
+    pub key: Identifier,
+    pub payload: Option<Vec<u8>>,
+}
+
+impl ServerboundPacket for CookieResponse {
+    const PACKET_ID: i32 = 0x14;
+
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { key: raw.data.read()?, payload: cookie::read_cookie_payload(&mut raw.data)? })
+    }
+
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_cookie(self.key.clone(), self.payload.clone())
+    }
+}
+
+#[derive(Debug)]
+pub struct PluginMessage {
+    pub channel: Identifier,
+    pub data: Vec<u8>,
+}
+
+impl ServerboundPacket for PluginMessage {
+    const PACKET_ID: i32 = 0x15;
+
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        let channel = raw.data.read()?;
+        Ok(Self { channel, data: plugin_message::read_plugin_message_data(&mut raw.data)? })
+    }
+
+    fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
+        conn.receive_plugin_message(self.channel.clone(), self.data.clone())
+    }
+}
+
+#[derive(Debug)]
+pub struct KeepAlive {
+    pub id: i64,
+}
+
+impl ServerboundPlayPacket for KeepAlive {
+    const PACKET_ID: i32 = 0x1B;
+
+    fn decode(mut raw: RawPacket) -> KeisteenResult<Self> {
+        Ok(Self { id: raw.data.read()? })
+    }
+
+    fn handle(&self, server: &mut Server, connection: ConnectionId) -> KeisteenResult<()> {
+        let Some(player) = server.player_list_mut().get_player_by_connection_mut(connection) else {
+            return Ok(());
+        };
+
+        if !player.keep_alive_mut().receive(self.id) {
+            server.disconnect(connection, Some(keep_alive::KeepAlive::timeout_reason()));
+        }
+
+        Ok(())
+    }
+}
@@ -26,42 +26,30 @@ impl ServerboundPacket for StatusRequest {
     }
 
     fn handle(&self, conn: &mut Connection) -> KeisteenResult<()> {
-        let (max, online, sample) = conn.server().read(|server| {
-            let player_list = server.player_list();
-            let max = player_list.max_players() as i32;
-            let online = player_list.online_players() as i32;
-            let sample = if online > 0 {
-                let players = player_list.players().iter().take(12);
-                let sample = players
-                    .map(|p| StatusResponsePlayerSample {
-                        name: p.profile().username().to_string(),
-                        id: p.profile().uuid(),
-                    })
-                    .collect();
-                Some(sample)
-            } else {
-                None
-            };
-
-            (max, online, sample)
-        });
-
-        let (motd, enforces_secure_chat) = conn.server().read(|server| {
-            let config = server.server_folder().config();
-            let motd = config.properties().motd.clone();
-            let enforces_secure_chat = config.enforces_secure_chat();
-            (motd, enforces_secure_chat)
-        });
+        let status = conn.server().read(|server| server.status());
 
         let json_response = serde_json::to_string(&StatusResponse {
             version: StatusResponseVersion {
-                name: crate::MC_VERSION.to_string(),
-                protocol: Some(crate::MC_PROTOCOL.raw()),
+                name: status.version_name,
+                protocol: Some(status.protocol),
             },
-            players: Some(StatusResponsePlayers { max, online, sample }),
-            description: Some(TextComponent { text: Some(motd), translate: None, color: None }),
-            favicon: None,
-            enforces_secure_chat,
+            players: status.players.map(|players| StatusResponsePlayers {
+                max: players.max,
+                online: players.online,
+                sample: (!players.sample.is_empty()).then(|| {
+                    players
+                        .sample
+                        .into_iter()
+                        .map(|sample| StatusResponsePlayerSample {
+                            name: sample.name,
+                            id: sample.id,
+                        })
+                        .collect()
+                }),
+            }),
+            description: Some(status.description),
+            favicon: status.favicon,
+            enforces_secure_chat: status.enforces_secure_chat,
         })
         .expect("should serialize status response");
 
@@ -0,0 +1,205 @@
+use std::io::IsTerminal;
+
+use crate::mc::text::language::{FormatSegment, Language, parse_format};
+use crate::mc::text::style::Style;
+use crate::mc::text::text_component::{Content, TextComponent};
+
+const ESCAPE: &str = "\x1b[";
+const RESET: &str = "\x1b[0m";
+
+/// Renders text components as text for a terminal, with their colors and decorations as ANSI
+/// escape codes.
+#[derive(Debug, Clone, Copy)]
+pub struct AnsiRenderer<'a> {
+    language: &'a Language,
+    colors: bool,
+}
+
+impl AnsiRenderer<'static> {
+    /// A renderer for the console, which resolves translations with the bundled `en_us` language
+    /// and only writes escape codes when stderr, where the log is written, is a terminal.
+    pub fn console() -> Self {
+        Self::new(Language::en_us(), std::io::stderr().is_terminal())
+    }
+}
+
+impl<'a> AnsiRenderer<'a> {
+    /// Creates a renderer that resolves translations with `language`, and writes plain text if
+    /// `colors` is false.
+    pub fn new(language: &'a Language, colors: bool) -> Self {
+        Self { language, colors }
+    }
+
+    /// Renders the component and its children. Translations that the language does not have
+    /// are written as their fallback or key, and missing arguments are left out.
+    pub fn render(&self, component: &TextComponent) -> String {
+        let mut output = String::new();
+        let mut current = Formatting::default();
+        self.write_component(component, &Style::default(), &mut current, &mut output);
+
+        if current != Formatting::default() {
+            output.push_str(RESET);
+        }
+        output
+    }
+
+    fn write_component(
+        &self,
+        component: &TextComponent,
+        parent: &Style,
+        current: &mut Formatting,
+        output: &mut String,
+    ) {
+        let style = component.style.inherit(parent);
+
+        match &component.content {
+            Content::Text(text) => self.write_text(text, &style, current, output),
+            Content::Translatable { key, fallback, with } => match self.language.get(key) {
+                Some(format) => {
+                    for segment in parse_format(format) {
+                        match segment {
+                            FormatSegment::Text(text) => {
+                                self.write_text(text, &style, current, output)
+                            }
+                            FormatSegment::Argument(index) => {
+                                if let Some(argument) = with.get(index) {
+                                    self.write_component(argument, &style, current, output);
+                                }
+                            }
+                        }
+                    }
+                }
+                None => {
+                    let text = fallback.as_deref().unwrap_or(key);
+                    self.write_text(text, &style, current, output);
+                }
+            },
+            // The client shows the key that is bound, but the name of the binding is the closest
+            // the server can get.
+            Content::Keybind(key) => {
+                let text = self.language.get(key).unwrap_or(key);
+                self.write_text(text, &style, current, output);
+            }
+            content => self.write_text(&content.to_string(), &style, current, output),
+        }
+
+        for child in &component.extra {
+            self.write_component(child, &style, current, output);
+        }
+    }
+
+    fn write_text(&self, text: &str, style: &Style, current: &mut Formatting, output: &mut String) {
+        if text.is_empty() {
+            return;
+        }
+
+        if self.colors {
+            let target = Formatting::from(style);
+            if target != *current {
+                target.write_escape_code(output);
+                *current = target;
+            }
+        }
+
+        output.push_str(text);
+    }
+}
+
+/// The part of a style that terminals can show.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Formatting {
+    color: Option<u32>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+}
+
+impl Formatting {
+    /// Writes an escape code that resets the previous formatting and applies this one.
+    fn write_escape_code(&self, output: &mut String) {
+        output.push_str(ESCAPE);
+        output.push('0');
+
+        for (is_set, code) in [
+            (self.bold, "1"),
+            (self.italic, "3"),
+            (self.underlined, "4"),
+            (self.strikethrough, "9"),
+        ] {
+            if is_set {
+                output.push(';');
+                output.push_str(code);
+            }
+        }
+
+        if let Some(color) = self.color {
+            let [_, red, green, blue] = color.to_be_bytes();
+            output.push_str(&format!(";38;2;{red};{green};{blue}"));
+        }
+
+        output.push('m');
+    }
+}
+
+impl From<&Style> for Formatting {
+    fn from(style: &Style) -> Self {
+        Self {
+            color: style.color.map(|color| color.value()),
+            bold: style.bold.unwrap_or(false),
+            italic: style.italic.unwrap_or(false),
+            underlined: style.underlined.unwrap_or(false),
+            strikethrough: style.strikethrough.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mc::text::color::NamedColor;
+
+    fn language() -> Language {
+        Language::from_json(r#"{"greeting": "Hello %s, you have %2$s"}"#).unwrap()
+    }
+
+    fn message() -> TextComponent {
+        let name = TextComponent::text("Steve").with_color(NamedColor::Red);
+        let count = TextComponent::text("3 mails");
+        let style = Style { bold: Some(true), ..Style::default() };
+        TextComponent::translatable("greeting", vec![name, count])
+            .with_style(style)
+            .append(TextComponent::text("!"))
+    }
+
+    #[test]
+    fn renders_plain_text_without_colors() {
+        let language = language();
+        let renderer = AnsiRenderer::new(&language, false);
+        assert_eq!(renderer.render(&message()), "Hello Steve, you have 3 mails!");
+    }
+
+    #[test]
+    fn renders_escape_codes_with_colors() {
+        let language = language();
+        let renderer = AnsiRenderer::new(&language, true);
+        assert_eq!(
+            renderer.render(&message()),
+            "\x1b[0;1mHello \x1b[0;1;38;2;255;85;85mSteve\x1b[0;1m, you have 3 mails!\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn renders_unknown_translations_as_their_fallback_or_key() {
+        let language = Language::default();
+        let renderer = AnsiRenderer::new(&language, false);
+
+        let mut component = TextComponent::translatable("missing.key", vec![]);
+        assert_eq!(renderer.render(&component), "missing.key");
+
+        if let Content::Translatable { fallback, .. } = &mut component.content {
+            *fallback = Some("Fallback".to_string());
+        }
+        assert_eq!(renderer.render(&component), "Fallback");
+    }
+}
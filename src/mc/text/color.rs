@@ -0,0 +1,209 @@
+use std::fmt;
+use std::str::FromStr;
+
+use eyre::bail;
+
+use crate::error::KeisteenError;
+
+/// The color of text, either one of the 16 named colors or any RGB color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextColor {
+    Named(NamedColor),
+    Rgb(u32),
+}
+
+impl TextColor {
+    /// Creates an RGB color from its components.
+    pub fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::Rgb(u32::from_be_bytes([0, red, green, blue]))
+    }
+
+    /// The RGB value of the color, as `0xRRGGBB`.
+    pub fn value(&self) -> u32 {
+        match self {
+            Self::Named(color) => color.value(),
+            Self::Rgb(value) => *value,
+        }
+    }
+}
+
+impl From<NamedColor> for TextColor {
+    fn from(color: NamedColor) -> Self {
+        Self::Named(color)
+    }
+}
+
+impl FromStr for TextColor {
+    type Err = KeisteenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix('#') {
+            if hex.len() != 6 {
+                bail!("invalid hex color: {s}");
+            }
+            return match u32::from_str_radix(hex, 16) {
+                Ok(value) => Ok(Self::Rgb(value)),
+                Err(_) => bail!("invalid hex color: {s}"),
+            };
+        }
+
+        s.parse().map(Self::Named)
+    }
+}
+
+impl fmt::Display for TextColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(color) => write!(f, "{color}"),
+            Self::Rgb(value) => write!(f, "#{value:06X}"),
+        }
+    }
+}
+
+impl serde::Serialize for TextColor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TextColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        string.parse().map_err(|err| serde::de::Error::custom(format!("{err}")))
+    }
+}
+
+/// The 16 colors that can also be selected with legacy formatting codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedColor {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl NamedColor {
+    pub const ALL: [NamedColor; 16] = [
+        Self::Black,
+        Self::DarkBlue,
+        Self::DarkGreen,
+        Self::DarkAqua,
+        Self::DarkRed,
+        Self::DarkPurple,
+        Self::Gold,
+        Self::Gray,
+        Self::DarkGray,
+        Self::Blue,
+        Self::Green,
+        Self::Aqua,
+        Self::Red,
+        Self::LightPurple,
+        Self::Yellow,
+        Self::White,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Black => "black",
+            Self::DarkBlue => "dark_blue",
+            Self::DarkGreen => "dark_green",
+            Self::DarkAqua => "dark_aqua",
+            Self::DarkRed => "dark_red",
+            Self::DarkPurple => "dark_purple",
+            Self::Gold => "gold",
+            Self::Gray => "gray",
+            Self::DarkGray => "dark_gray",
+            Self::Blue => "blue",
+            Self::Green => "green",
+            Self::Aqua => "aqua",
+            Self::Red => "red",
+            Self::LightPurple => "light_purple",
+            Self::Yellow => "yellow",
+            Self::White => "white",
+        }
+    }
+
+    /// The character that selects the color in legacy formatting codes.
+    pub fn code(&self) -> char {
+        match self {
+            Self::Black => '0',
+            Self::DarkBlue => '1',
+            Self::DarkGreen => '2',
+            Self::DarkAqua => '3',
+            Self::DarkRed => '4',
+            Self::DarkPurple => '5',
+            Self::Gold => '6',
+            Self::Gray => '7',
+            Self::DarkGray => '8',
+            Self::Blue => '9',
+            Self::Green => 'a',
+            Self::Aqua => 'b',
+            Self::Red => 'c',
+            Self::LightPurple => 'd',
+            Self::Yellow => 'e',
+            Self::White => 'f',
+        }
+    }
+
+    pub fn from_code(code: char) -> Option<Self> {
+        let code = code.to_ascii_lowercase();
+        Self::ALL.into_iter().find(|color| color.code() == code)
+    }
+
+    /// The RGB value the client renders the color with, as `0xRRGGBB`.
+    pub fn value(&self) -> u32 {
+        match self {
+            Self::Black => 0x000000,
+            Self::DarkBlue => 0x0000AA,
+            Self::DarkGreen => 0x00AA00,
+            Self::DarkAqua => 0x00AAAA,
+            Self::DarkRed => 0xAA0000,
+            Self::DarkPurple => 0xAA00AA,
+            Self::Gold => 0xFFAA00,
+            Self::Gray => 0xAAAAAA,
+            Self::DarkGray => 0x555555,
+            Self::Blue => 0x5555FF,
+            Self::Green => 0x55FF55,
+            Self::Aqua => 0x55FFFF,
+            Self::Red => 0xFF5555,
+            Self::LightPurple => 0xFF55FF,
+            Self::Yellow => 0xFFFF55,
+            Self::White => 0xFFFFFF,
+        }
+    }
+}
+
+impl FromStr for NamedColor {
+    type Err = KeisteenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::ALL.into_iter().find(|color| color.name() == s) {
+            Some(color) => Ok(color),
+            None => bail!("unknown color: {s}"),
+        }
+    }
+}
+
+impl fmt::Display for NamedColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
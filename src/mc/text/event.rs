@@ -0,0 +1,64 @@
+use uuid::Uuid;
+
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::Identifier;
+
+/// What happens when the player clicks on text.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClickEvent {
+    OpenUrl {
+        url: String,
+    },
+    /// Only allowed in chat. Clients ask for confirmation before opening files.
+    OpenFile {
+        path: String,
+    },
+    RunCommand {
+        command: String,
+    },
+    SuggestCommand {
+        command: String,
+    },
+    /// Only allowed in books.
+    ChangePage {
+        page: i32,
+    },
+    CopyToClipboard {
+        value: String,
+    },
+    /// Sends a custom click action packet back to the server.
+    Custom {
+        id: Identifier,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payload: Option<String>,
+    },
+}
+
+/// What is shown when the player hovers over text.
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum HoverEvent {
+    ShowText {
+        value: Box<TextComponent>,
+    },
+    ShowItem {
+        id: Identifier,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        count: Option<i32>,
+    },
+    ShowEntity {
+        id: Identifier,
+        uuid: Uuid,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<Box<TextComponent>>,
+    },
+}
+
+impl HoverEvent {
+    pub fn show_text(text: TextComponent) -> Self {
+        Self::ShowText { value: Box::new(text) }
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use eyre::{Context, bail};
+
+use crate::error::KeisteenResult;
+
+/// The English translations of the keys the server itself uses, for rendering text components
+/// outside the client.
+const EN_US: &str = include_str!("../../../assets/lang/en_us.json");
+
+/// A table of translation keys to format strings, like the client's language files.
+#[derive(Debug, Clone, Default)]
+#[derive(serde::Deserialize)]
+#[serde(transparent)]
+pub struct Language {
+    translations: HashMap<String, String>,
+}
+
+impl Language {
+    /// The bundled `en_us` language.
+    pub fn en_us() -> &'static Language {
+        static EN_US_LANGUAGE: OnceLock<Language> = OnceLock::new();
+        EN_US_LANGUAGE
+            .get_or_init(|| Self::from_json(EN_US).expect("bundled en_us language should be valid"))
+    }
+
+    /// Parses a language file, which is a JSON object of translation keys to format strings.
+    pub fn from_json(json: &str) -> KeisteenResult<Self> {
+        serde_json::from_str(json).wrap_err("failed to parse language file")
+    }
+
+    /// Parses a TOML language file. Keys of nested tables are joined with `.`, so
+    /// `[multiplayer.player] joined = "..."` defines `multiplayer.player.joined`.
+    pub fn from_toml(toml: &str) -> KeisteenResult<Self> {
+        let table: toml::Table = toml::from_str(toml).wrap_err("failed to parse language file")?;
+
+        let mut language = Self::default();
+        language.insert_table("", table)?;
+        Ok(language)
+    }
+
+    fn insert_table(&mut self, prefix: &str, table: toml::Table) -> KeisteenResult<()> {
+        for (key, value) in table {
+            let key = if prefix.is_empty() { key } else { format!("{prefix}.{key}") };
+            match value {
+                toml::Value::String(format) => {
+                    self.translations.insert(key, format);
+                }
+                toml::Value::Table(table) => self.insert_table(&key, table)?,
+                _ => bail!("translation '{key}' is not a string"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds the translations of another language, replacing the ones with the same keys.
+    pub fn extend(&mut self, other: Language) {
+        self.translations.extend(other.translations);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.translations.get(key).map(String::as_str)
+    }
+}
+
+/// A part of a translation format string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatSegment<'a> {
+    Text(&'a str),
+    /// The argument with the given index, from `%s` or `%1$s`.
+    Argument(usize),
+}
+
+/// Splits a format string into text and arguments. `%s` takes the next argument, `%1$s` the
+/// first, and `%%` is a literal `%`. Other uses of `%` are kept as text.
+pub fn parse_format(format: &str) -> Vec<FormatSegment<'_>> {
+    let mut segments = Vec::new();
+    let mut next_argument = 0;
+    let mut text_start = 0;
+
+    let mut index = 0;
+    while let Some(offset) = format[index..].find('%') {
+        let start = index + offset;
+        let specifier = &format[start + 1..];
+        let digits = specifier.bytes().take_while(u8::is_ascii_digit).count();
+
+        let (segment, length) = if specifier.starts_with('%') {
+            (FormatSegment::Text("%"), 2)
+        } else if specifier.starts_with('s') {
+            next_argument += 1;
+            (FormatSegment::Argument(next_argument - 1), 2)
+        } else if let Some(argument) = specifier[digits..]
+            .starts_with("$s")
+            .then(|| specifier[..digits].parse::<usize>().ok()?.checked_sub(1))
+            .flatten()
+        {
+            (FormatSegment::Argument(argument), digits + 3)
+        } else {
+            index = start + 1;
+            continue;
+        };
+
+        if text_start < start {
+            segments.push(FormatSegment::Text(&format[text_start..start]));
+        }
+        segments.push(segment);
+
+        index = start + length;
+        text_start = index;
+    }
+
+    if text_start < format.len() {
+        segments.push(FormatSegment::Text(&format[text_start..]));
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use FormatSegment::{Argument, Text};
+
+    #[test]
+    fn parses_sequential_arguments() {
+        assert_eq!(parse_format("%s joined %s"), vec![Argument(0), Text(" joined "), Argument(1)]);
+    }
+
+    #[test]
+    fn parses_positional_arguments() {
+        assert_eq!(
+            parse_format("%2$s before %1$s"),
+            vec![Argument(1), Text(" before "), Argument(0)]
+        );
+    }
+
+    #[test]
+    fn parses_escaped_percent_signs() {
+        assert_eq!(
+            parse_format("100%% of %s"),
+            vec![Text("100"), Text("%"), Text(" of "), Argument(0)]
+        );
+    }
+
+    #[test]
+    fn keeps_invalid_specifiers_as_text() {
+        assert_eq!(parse_format("trailing %"), vec![Text("trailing %")]);
+        assert_eq!(parse_format("%0$s and %d"), vec![Text("%0$s and %d")]);
+        assert_eq!(parse_format("%1$d"), vec![Text("%1$d")]);
+    }
+
+    #[test]
+    fn joins_nested_toml_keys() {
+        let language = Language::from_toml("[multiplayer.player]\njoined = \"%s joined\"").unwrap();
+        assert_eq!(language.get("multiplayer.player.joined"), Some("%s joined"));
+        assert!(Language::from_toml("key = 1").is_err());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::mc::text::color::{NamedColor, TextColor};
+use crate::mc::text::style::Style;
+use crate::mc::text::text_component::TextComponent;
+
+/// The prefix of formatting codes that the client itself understands.
+pub const SECTION_SIGN: char = '§';
+
+const OBFUSCATED: char = 'k';
+const BOLD: char = 'l';
+const STRIKETHROUGH: char = 'm';
+const UNDERLINED: char = 'n';
+const ITALIC: char = 'o';
+const RESET: char = 'r';
+// Hex colors are written as `&#RRGGBB`, or as `&x&R&R&G&G&B&B` like BungeeCord does.
+const HEX: char = '#';
+const BUNGEECORD_HEX: char = 'x';
+
+/// Converts between strings with legacy formatting codes, like `&a` or `§l`, and text
+/// components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LegacyFormat {
+    prefix: char,
+}
+
+impl LegacyFormat {
+    pub const SECTION: Self = Self { prefix: SECTION_SIGN };
+    /// Codes prefixed with `&`, which are easier to type in config files.
+    pub const AMPERSAND: Self = Self { prefix: '&' };
+
+    pub fn new(prefix: char) -> Self {
+        Self { prefix }
+    }
+
+    pub fn prefix(&self) -> char {
+        self.prefix
+    }
+
+    /// Parses a string with formatting codes. Codes prefixed with the section sign are always
+    /// recognized, and prefixes that are not followed by a valid code are kept as text.
+    pub fn parse(&self, text: &str) -> TextComponent {
+        let chars: Vec<char> = text.chars().collect();
+
+        let mut segments = Vec::new();
+        let mut style = Style::default();
+        let mut segment = String::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let char = chars[index];
+            if (char == self.prefix || char == SECTION_SIGN)
+                && let Some((next_style, length)) = self.parse_code(&chars[index + 1..], &style)
+            {
+                if !segment.is_empty() {
+                    let text = std::mem::take(&mut segment);
+                    segments.push(TextComponent::text(text).with_style(style));
+                }
+                style = next_style;
+                index += 1 + length;
+                continue;
+            }
+
+            segment.push(char);
+            index += 1;
+        }
+
+        if !segment.is_empty() {
+            segments.push(TextComponent::text(segment).with_style(style));
+        }
+
+        if segments.len() == 1 {
+            return segments.remove(0);
+        }
+
+        let mut component = TextComponent::text("");
+        component.extra = segments;
+        component
+    }
+
+    /// Parses the code after a prefix, returning the resulting style and the number of characters
+    /// the code takes up.
+    fn parse_code(&self, code: &[char], style: &Style) -> Option<(Style, usize)> {
+        let (&first, rest) = code.split_first()?;
+
+        // Like the client, colors reset all other formatting.
+        if first == HEX {
+            let hex: String = rest.get(..6)?.iter().collect();
+            let color = parse_hex(&hex)?;
+            return Some((Style { color: Some(color), ..Style::default() }, 7));
+        }
+
+        if first.eq_ignore_ascii_case(&BUNGEECORD_HEX) {
+            let pairs = rest.get(..12)?;
+            if !pairs.chunks(2).all(|pair| pair[0] == self.prefix || pair[0] == SECTION_SIGN) {
+                return None;
+            }
+            let hex: String = pairs.chunks(2).map(|pair| pair[1]).collect();
+            let color = parse_hex(&hex)?;
+            return Some((Style { color: Some(color), ..Style::default() }, 13));
+        }
+
+        if let Some(color) = NamedColor::from_code(first) {
+            return Some((Style { color: Some(color.into()), ..Style::default() }, 1));
+        }
+
+        let mut style = style.clone();
+        match first.to_ascii_lowercase() {
+            OBFUSCATED => style.obfuscated = Some(true),
+            BOLD => style.bold = Some(true),
+            STRIKETHROUGH => style.strikethrough = Some(true),
+            UNDERLINED => style.underlined = Some(true),
+            ITALIC => style.italic = Some(true),
+            RESET => style = Style::default(),
+            _ => return None,
+        }
+
+        Some((style, 1))
+    }
+
+    /// Writes the component as a string with formatting codes. Formatting that has no code, like
+    /// click events or fonts, is lost.
+    pub fn serialize(&self, component: &TextComponent) -> String {
+        let mut output = String::new();
+        let mut current = Formatting::default();
+        self.write_component(component, &Style::default(), &mut current, &mut output);
+        output
+    }
+
+    fn write_component(
+        &self,
+        component: &TextComponent,
+        parent: &Style,
+        current: &mut Formatting,
+        output: &mut String,
+    ) {
+        let style = component.style.inherit(parent);
+
+        let text = component.content.to_string();
+        if !text.is_empty() {
+            self.write_formatting(current, Formatting::from(&style), output);
+            output.push_str(&text);
+        }
+
+        for child in &component.extra {
+            self.write_component(child, &style, current, output);
+        }
+    }
+
+    fn write_formatting(&self, current: &mut Formatting, target: Formatting, output: &mut String) {
+        if *current == target {
+            return;
+        }
+
+        // Formatting can only be removed by resetting it, which colors do as well.
+        let added = if target.color != current.color || current.has_codes_missing_from(&target) {
+            match target.color {
+                Some(TextColor::Named(color)) => self.write_code(color.code(), output),
+                // The section sign is usually read by other software, which only knows the
+                // BungeeCord format.
+                Some(TextColor::Rgb(value)) if self.prefix == SECTION_SIGN => {
+                    self.write_code(BUNGEECORD_HEX, output);
+                    for digit in format!("{value:06X}").chars() {
+                        self.write_code(digit, output);
+                    }
+                }
+                Some(TextColor::Rgb(value)) => {
+                    output.push(self.prefix);
+                    output.push_str(&format!("{HEX}{value:06X}"));
+                }
+                None => self.write_code(RESET, output),
+            }
+            target.codes()
+        } else {
+            target.codes().into_iter().filter(|code| !current.codes().contains(code)).collect()
+        };
+
+        for code in added {
+            self.write_code(code, output);
+        }
+
+        *current = target;
+    }
+
+    fn write_code(&self, code: char, output: &mut String) {
+        output.push(self.prefix);
+        output.push(code);
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<TextColor> {
+    if hex.len() != 6 || !hex.chars().all(|char| char.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    u32::from_str_radix(hex, 16).ok().map(TextColor::Rgb)
+}
+
+/// The part of a style that formatting codes can express.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Formatting {
+    color: Option<TextColor>,
+    obfuscated: bool,
+    bold: bool,
+    strikethrough: bool,
+    underlined: bool,
+    italic: bool,
+}
+
+impl Formatting {
+    fn codes(&self) -> Vec<char> {
+        [
+            (self.obfuscated, OBFUSCATED),
+            (self.bold, BOLD),
+            (self.strikethrough, STRIKETHROUGH),
+            (self.underlined, UNDERLINED),
+            (self.italic, ITALIC),
+        ]
+        .into_iter()
+        .filter_map(|(is_set, code)| is_set.then_some(code))
+        .collect()
+    }
+
+    fn has_codes_missing_from(&self, target: &Formatting) -> bool {
+        let target_codes = target.codes();
+        self.codes().iter().any(|code| !target_codes.contains(code))
+    }
+}
+
+impl From<&Style> for Formatting {
+    fn from(style: &Style) -> Self {
+        Self {
+            color: style.color,
+            obfuscated: style.obfuscated.unwrap_or(false),
+            bold: style.bold.unwrap_or(false),
+            strikethrough: style.strikethrough.unwrap_or(false),
+            underlined: style.underlined.unwrap_or(false),
+            italic: style.italic.unwrap_or(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str, style: Style) -> TextComponent {
+        TextComponent::text(text).with_style(style)
+    }
+
+    fn colored(color: impl Into<TextColor>) -> Style {
+        Style { color: Some(color.into()), ..Style::default() }
+    }
+
+    fn segments(component: TextComponent) -> Vec<TextComponent> {
+        if component.extra.is_empty() { vec![component] } else { component.extra }
+    }
+
+    #[test]
+    fn parses_section_and_ampersand_codes() {
+        let expected = vec![
+            text("Hello ", colored(NamedColor::Green)),
+            text("world", Style { bold: Some(true), ..colored(NamedColor::Green) }),
+        ];
+        assert_eq!(segments(LegacyFormat::SECTION.parse("§aHello §lworld")), expected);
+        assert_eq!(segments(LegacyFormat::AMPERSAND.parse("&aHello &Lworld")), expected);
+        // The section sign is understood by the client, so it is always recognized.
+        assert_eq!(segments(LegacyFormat::AMPERSAND.parse("§aHello &lworld")), expected);
+    }
+
+    #[test]
+    fn keeps_invalid_codes_as_text() {
+        assert_eq!(
+            LegacyFormat::AMPERSAND.parse("Tom & Jerry &z"),
+            TextComponent::text("Tom & Jerry &z")
+        );
+        assert_eq!(LegacyFormat::SECTION.parse("a &a b §"), TextComponent::text("a &a b §"));
+    }
+
+    #[test]
+    fn resets_formatting() {
+        let component = LegacyFormat::SECTION.parse("§c§obad§r good");
+        let expected = vec![
+            text("bad", Style { italic: Some(true), ..colored(NamedColor::Red) }),
+            TextComponent::text(" good"),
+        ];
+        assert_eq!(segments(component), expected);
+    }
+
+    #[test]
+    fn colors_reset_decorations() {
+        let component = LegacyFormat::AMPERSAND.parse("&l&nbold&6gold");
+        let expected = vec![
+            text("bold", Style { bold: Some(true), underlined: Some(true), ..Style::default() }),
+            text("gold", colored(NamedColor::Gold)),
+        ];
+        assert_eq!(segments(component), expected);
+    }
+
+    #[test]
+    fn parses_hex_colors() {
+        let expected = text("hex", colored(TextColor::Rgb(0x12AB9F)));
+        assert_eq!(LegacyFormat::SECTION.parse("§x§1§2§a§b§9§fhex"), expected);
+        assert_eq!(LegacyFormat::AMPERSAND.parse("&x&1&2&A&B&9&Fhex"), expected);
+        assert_eq!(LegacyFormat::AMPERSAND.parse("&#12ab9fhex"), expected);
+
+        // Incomplete sequences are not hex colors, but the codes in them still apply.
+        let component = LegacyFormat::SECTION.parse("§x§1§2hex");
+        let expected = vec![TextComponent::text("§x"), text("hex", colored(NamedColor::DarkGreen))];
+        assert_eq!(segments(component), expected);
+        assert_eq!(LegacyFormat::AMPERSAND.parse("&#12ab"), TextComponent::text("&#12ab"));
+    }
+
+    #[test]
+    fn serializes_codes() {
+        let component = TextComponent::text("")
+            .append(text("Hello ", colored(NamedColor::Green)))
+            .append(text("world", Style { bold: Some(true), ..colored(NamedColor::Green) }))
+            .append(text("!", Style::default()))
+            .append(text("hex", colored(TextColor::Rgb(0x12AB9F))));
+
+        assert_eq!(
+            LegacyFormat::SECTION.serialize(&component),
+            "§aHello §lworld§r!§x§1§2§A§B§9§Fhex"
+        );
+        assert_eq!(LegacyFormat::AMPERSAND.serialize(&component), "&aHello &lworld&r!&#12AB9Fhex");
+    }
+
+    #[test]
+    fn round_trips_through_codes() {
+        for input in ["§aHello §lworld§r!", "§x§1§2§A§B§9§Fhex §nunder", "plain"] {
+            let component = LegacyFormat::SECTION.parse(input);
+            assert_eq!(LegacyFormat::SECTION.serialize(&component), input);
+        }
+    }
+}
@@ -0,0 +1,720 @@
+// Tag based markup for text components, modelled after Adventure's MiniMessage.
+// https://docs.advntr.dev/minimessage/format.html
+
+use std::fmt;
+
+use crate::mc::text::color::TextColor;
+use crate::mc::text::event::{ClickEvent, HoverEvent};
+use crate::mc::text::style::Style;
+use crate::mc::text::text_component::{Content, TextComponent};
+
+/// Malformed markup, with the byte offset in the input at which it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    position: usize,
+    message: String,
+}
+
+impl MarkupError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self { position, message: message.into() }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// Parses markup like `<red>Hello <bold><hover:show_text:'hi'>world</hover></bold>` into a text
+/// component. Tags that are still open at the end of the input are closed implicitly, and `<` can
+/// be escaped as `\<`. Like in MiniMessage, a `<` that does not start a tag, because it is followed
+/// by whitespace or never closed by a `>`, is kept as text, and so are unknown tags and closing
+/// tags without an open tag, so that text like `1<2 and 3>2` is shown as it is written.
+///
+/// Supported tags are colors (`<red>`, `<#ff0000>`, `<color:red>`), decorations (`<bold>`,
+/// `<!italic>`, ...), `<gradient:color:color...>`, `<click:action:value>`,
+/// `<hover:show_text:markup>`, `<insert:text>`, `<font:id>`, `<reset>`, `<newline>` and the
+/// content tags `<lang:key:args...>`, `<key:keybind>`, `<score:name:objective>` and
+/// `<selector:selector>`.
+pub fn parse(input: &str) -> Result<TextComponent, MarkupError> {
+    parse_at(input, 0)
+}
+
+/// Writes a text component as markup. Gradients are written as the colors of their characters,
+/// and formatting that has no tag, like shadow colors or other hover events than `show_text`, is
+/// lost.
+pub fn serialize(component: &TextComponent) -> String {
+    let mut output = String::new();
+    write_component(component, &mut output);
+    output
+}
+
+/// A tag that applies to the components up to its closing tag.
+enum Tag {
+    Style(Box<Style>),
+    Gradient(Vec<TextColor>),
+}
+
+struct OpenTag {
+    name: String,
+    tag: Tag,
+    children: Vec<TextComponent>,
+}
+
+impl OpenTag {
+    fn close(self) -> TextComponent {
+        match self.tag {
+            Tag::Style(style) => {
+                // A single child is styled directly, as the style it inherits renders the same.
+                let mut children = self.children;
+                if children.len() == 1 {
+                    let mut child = children.remove(0);
+                    child.style = Box::new(child.style.inherit(&style));
+                    return child;
+                }
+
+                let mut component = TextComponent::text("").with_style(*style);
+                component.extra = children;
+                component
+            }
+            Tag::Gradient(colors) => {
+                let mut children = self.children;
+                let mut component = if children.len() == 1 {
+                    children.remove(0)
+                } else {
+                    let mut component = TextComponent::text("");
+                    component.extra = children;
+                    component
+                };
+
+                let length = gradient_length(&component);
+                apply_gradient(&mut component, &colors, length, &mut 0);
+                component
+            }
+        }
+    }
+}
+
+struct Argument {
+    value: String,
+    /// The byte offset of the argument in the input.
+    position: usize,
+}
+
+/// The contents of a tag between `<` and `>`, split into its name and arguments.
+struct RawTag {
+    is_closing: bool,
+    arguments: Vec<Argument>,
+    /// The byte offset after the closing `>`.
+    end: usize,
+}
+
+fn parse_at(input: &str, offset: usize) -> Result<TextComponent, MarkupError> {
+    let mut root = Vec::new();
+    let mut open_tags: Vec<OpenTag> = Vec::new();
+    let mut text = String::new();
+
+    let mut index = 0;
+    while let Some(char) = input[index..].chars().next() {
+        match char {
+            '\\' if matches!(input[index + 1..].chars().next(), Some('<' | '\\')) => {
+                text.push(input.as_bytes()[index + 1] as char);
+                index += 2;
+            }
+            '<' => {
+                let Some(raw_tag) = scan_tag(input, index, offset)? else {
+                    text.push(char);
+                    index += 1;
+                    continue;
+                };
+
+                let is_known = handle_tag(
+                    raw_tag.arguments,
+                    raw_tag.is_closing,
+                    &mut text,
+                    &mut open_tags,
+                    &mut root,
+                )?;
+                if !is_known {
+                    text.push_str(&input[index..raw_tag.end]);
+                }
+                index = raw_tag.end;
+            }
+            _ => {
+                text.push(char);
+                index += char.len_utf8();
+            }
+        }
+    }
+
+    flush_text(&mut text, &mut open_tags, &mut root);
+    close_tags(&mut open_tags, &mut root, 0);
+
+    if root.len() == 1 {
+        return Ok(root.remove(0));
+    }
+
+    let mut component = TextComponent::text("");
+    component.extra = root;
+    Ok(component)
+}
+
+/// Reads the tag starting at `start`, splitting its arguments on `:`. Arguments can be quoted with
+/// `'` or `"` to contain `:` and `>`, in which case `\` escapes the next character.
+///
+/// Returns `None` if the `<` at `start` does not start a tag.
+fn scan_tag(input: &str, start: usize, offset: usize) -> Result<Option<RawTag>, MarkupError> {
+    if input[start + 1..].chars().next().is_none_or(char::is_whitespace) {
+        return Ok(None);
+    }
+
+    let mut arguments = Vec::new();
+    let mut value = String::new();
+    let mut value_position = start + 1;
+    let mut quote = None;
+    let mut is_argument_start = true;
+    let mut is_quoted = false;
+
+    let mut chars =
+        input[start + 1..].char_indices().map(|(index, char)| (start + 1 + index, char));
+    while let Some((index, char)) = chars.next() {
+        match (quote, char) {
+            (Some(_), '\\') => match chars.next() {
+                Some((_, escaped)) => value.push(escaped),
+                None => break,
+            },
+            (Some(quote_char), _) if char == quote_char => quote = None,
+            (Some(_), _) => value.push(char),
+            (None, '\'' | '"') if is_argument_start => {
+                quote = Some(char);
+                is_quoted = true;
+                value_position = index + 1;
+            }
+            (None, ':') => {
+                let value = std::mem::take(&mut value);
+                arguments.push(Argument { value, position: offset + value_position });
+                value_position = index + 1;
+                is_argument_start = true;
+                is_quoted = false;
+                continue;
+            }
+            (None, '>') => {
+                // Self-closing tags like `<newline/>` are accepted, but any tag without children
+                // closes itself.
+                if !is_quoted && value.ends_with('/') {
+                    value.pop();
+                }
+                arguments.push(Argument { value, position: offset + value_position });
+
+                let name = &mut arguments[0].value;
+                let is_closing = name.starts_with('/');
+                if is_closing {
+                    name.remove(0);
+                }
+
+                return Ok(Some(RawTag { is_closing, arguments, end: index + 1 }));
+            }
+            (None, _) => value.push(char),
+        }
+        is_argument_start = false;
+    }
+
+    match quote {
+        Some(_) => {
+            Err(MarkupError::new(offset + value_position - 1, "unterminated quoted argument"))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Adds the text read so far to the innermost open tag.
+fn flush_text(text: &mut String, open_tags: &mut [OpenTag], root: &mut Vec<TextComponent>) {
+    if !text.is_empty() {
+        let children = open_tags.last_mut().map_or(root, |tag| &mut tag.children);
+        children.push(TextComponent::text(std::mem::take(text)));
+    }
+}
+
+/// Applies a tag, after adding the text before it. Returns `false` without changing anything if
+/// the tag is unknown, in which case it is kept as text.
+fn handle_tag(
+    arguments: Vec<Argument>,
+    is_closing: bool,
+    text: &mut String,
+    open_tags: &mut Vec<OpenTag>,
+    root: &mut Vec<TextComponent>,
+) -> Result<bool, MarkupError> {
+    let name_position = arguments[0].position;
+    let name = arguments[0].value.to_lowercase();
+    let (is_negated, name) = match name.strip_prefix('!') {
+        Some(name) => (true, name.to_string()),
+        None => (false, name),
+    };
+    let name = canonical_name(&name).to_string();
+
+    if is_closing {
+        let Some(index) = open_tags.iter().rposition(|tag| tag.name == name) else {
+            return Ok(false);
+        };
+        flush_text(text, open_tags, root);
+        close_tags(open_tags, root, index);
+        return Ok(true);
+    }
+
+    let argument = |index: usize| -> Result<&Argument, MarkupError> {
+        arguments.get(index).ok_or_else(|| {
+            MarkupError::new(name_position, format!("tag '{name}' is missing an argument"))
+        })
+    };
+
+    // Tags that are replaced by content.
+    let content = match name.as_str() {
+        "reset" => {
+            flush_text(text, open_tags, root);
+            close_tags(open_tags, root, 0);
+            return Ok(true);
+        }
+        "newline" => Some(TextComponent::text("\n")),
+        "lang" => {
+            let key = argument(1)?.value.clone();
+            let with = arguments[2..]
+                .iter()
+                .map(|argument| parse_at(&argument.value, argument.position))
+                .collect::<Result<_, _>>()?;
+            Some(TextComponent::translatable(key, with))
+        }
+        "key" => Some(TextComponent::keybind(argument(1)?.value.clone())),
+        "score" => {
+            Some(TextComponent::score(argument(1)?.value.clone(), argument(2)?.value.clone()))
+        }
+        "selector" => Some(TextComponent::selector(argument(1)?.value.clone())),
+        _ => None,
+    };
+    if let Some(content) = content {
+        flush_text(text, open_tags, root);
+        open_tags.last_mut().map_or(root, |tag| &mut tag.children).push(content);
+        return Ok(true);
+    }
+
+    let mut style = Style::default();
+    let tag = match name.as_str() {
+        "color" => {
+            style.color = Some(parse_color(argument(1)?)?);
+            Tag::Style(Box::new(style))
+        }
+        "gradient" => {
+            let colors = arguments[1..].iter().map(parse_color).collect::<Result<Vec<_>, _>>()?;
+            if colors.len() < 2 {
+                return Err(MarkupError::new(name_position, "gradient needs at least two colors"));
+            }
+            Tag::Gradient(colors)
+        }
+        "bold" | "italic" | "underlined" | "strikethrough" | "obfuscated" => {
+            let value = match arguments.get(1).map(|argument| argument.value.as_str()) {
+                None => !is_negated,
+                Some("true") => !is_negated,
+                Some("false") => is_negated,
+                Some(_) => {
+                    let position = arguments[1].position;
+                    return Err(MarkupError::new(position, "expected 'true' or 'false'"));
+                }
+            };
+            let decoration = match name.as_str() {
+                "bold" => &mut style.bold,
+                "italic" => &mut style.italic,
+                "underlined" => &mut style.underlined,
+                "strikethrough" => &mut style.strikethrough,
+                _ => &mut style.obfuscated,
+            };
+            *decoration = Some(value);
+            Tag::Style(Box::new(style))
+        }
+        "click" => {
+            let action = argument(1)?;
+            let value = argument(2)?.value.clone();
+            style.click_event = Some(match action.value.as_str() {
+                "open_url" => ClickEvent::OpenUrl { url: value },
+                "open_file" => ClickEvent::OpenFile { path: value },
+                "run_command" => ClickEvent::RunCommand { command: value },
+                "suggest_command" => ClickEvent::SuggestCommand { command: value },
+                "copy_to_clipboard" => ClickEvent::CopyToClipboard { value },
+                "change_page" => {
+                    let page = value.parse().map_err(|_| {
+                        MarkupError::new(arguments[2].position, "expected a page number")
+                    })?;
+                    ClickEvent::ChangePage { page }
+                }
+                action_name => {
+                    let message = format!("unknown click action '{action_name}'");
+                    return Err(MarkupError::new(action.position, message));
+                }
+            });
+            Tag::Style(Box::new(style))
+        }
+        "hover" => {
+            let action = argument(1)?;
+            if action.value != "show_text" {
+                let message = format!("unsupported hover action '{}'", action.value);
+                return Err(MarkupError::new(action.position, message));
+            }
+            let text = argument(2)?;
+            style.hover_event = Some(HoverEvent::show_text(parse_at(&text.value, text.position)?));
+            Tag::Style(Box::new(style))
+        }
+        "insert" => {
+            style.insertion = Some(argument(1)?.value.clone());
+            Tag::Style(Box::new(style))
+        }
+        "font" => {
+            let font = argument(1)?;
+            let font = font
+                .value
+                .parse()
+                .map_err(|_| MarkupError::new(font.position, "invalid font identifier"))?;
+            style.font = Some(font);
+            Tag::Style(Box::new(style))
+        }
+        _ => match name.parse::<TextColor>() {
+            Ok(color) => {
+                style.color = Some(color);
+                Tag::Style(Box::new(style))
+            }
+            Err(_) => return Ok(false),
+        },
+    };
+
+    flush_text(text, open_tags, root);
+    open_tags.push(OpenTag { name, tag, children: Vec::new() });
+    Ok(true)
+}
+
+fn canonical_name(name: &str) -> &str {
+    match name {
+        "colour" | "c" => "color",
+        "b" => "bold",
+        "i" | "em" => "italic",
+        "u" => "underlined",
+        "st" => "strikethrough",
+        "obf" => "obfuscated",
+        "br" => "newline",
+        "tr" | "translate" => "lang",
+        "sel" => "selector",
+        "insertion" => "insert",
+        name => name,
+    }
+}
+
+/// Closes the tags from `index` onwards, innermost first.
+fn close_tags(open_tags: &mut Vec<OpenTag>, root: &mut Vec<TextComponent>, index: usize) {
+    while open_tags.len() > index {
+        let component = open_tags.pop().expect("tag should be open").close();
+        open_tags.last_mut().map_or(&mut *root, |tag| &mut tag.children).push(component);
+    }
+}
+
+fn parse_color(argument: &Argument) -> Result<TextColor, MarkupError> {
+    argument.value.to_lowercase().parse().map_err(|_| {
+        MarkupError::new(argument.position, format!("invalid color '{}'", argument.value))
+    })
+}
+
+/// The number of characters a gradient colors, which excludes text that has its own color.
+fn gradient_length(component: &TextComponent) -> usize {
+    if component.style.color.is_some() {
+        return 0;
+    }
+
+    let length = match &component.content {
+        Content::Text(text) => text.chars().count(),
+        _ => 0,
+    };
+    length + component.extra.iter().map(gradient_length).sum::<usize>()
+}
+
+fn apply_gradient(
+    component: &mut TextComponent,
+    colors: &[TextColor],
+    length: usize,
+    index: &mut usize,
+) {
+    if component.style.color.is_some() {
+        return;
+    }
+
+    let mut characters = Vec::new();
+    if let Content::Text(text) = &mut component.content {
+        for char in std::mem::take(text).chars() {
+            let color = gradient_color(colors, *index, length);
+            characters.push(TextComponent::text(char).with_color(color));
+            *index += 1;
+        }
+    }
+
+    for child in &mut component.extra {
+        apply_gradient(child, colors, length, index);
+    }
+
+    component.extra.splice(0..0, characters);
+}
+
+fn gradient_color(colors: &[TextColor], index: usize, length: usize) -> TextColor {
+    if length <= 1 {
+        return colors[0];
+    }
+
+    let progress = index as f64 / (length - 1) as f64 * (colors.len() - 1) as f64;
+    let segment = (progress as usize).min(colors.len() - 2);
+    let progress = progress - segment as f64;
+
+    let [_, from @ ..] = colors[segment].value().to_be_bytes();
+    let [_, to @ ..] = colors[segment + 1].value().to_be_bytes();
+    let channel =
+        |i: usize| (from[i] as f64 + (to[i] as f64 - from[i] as f64) * progress).round() as u8;
+
+    TextColor::rgb(channel(0), channel(1), channel(2))
+}
+
+fn write_component(component: &TextComponent, output: &mut String) {
+    let tags = style_tags(&component.style);
+    for (open, _) in &tags {
+        output.push_str(open);
+    }
+
+    match &component.content {
+        Content::Text(text) => write_escaped(text, output),
+        Content::Translatable { key, with, .. } => {
+            output.push_str("<lang:");
+            output.push_str(&quote(key));
+            for argument in with {
+                output.push(':');
+                output.push_str(&quote(&serialize(argument)));
+            }
+            output.push('>');
+        }
+        Content::Keybind(key) => output.push_str(&format!("<key:{}>", quote(key))),
+        Content::Score { name, objective } => {
+            output.push_str(&format!("<score:{}:{}>", quote(name), quote(objective)));
+        }
+        Content::Selector { selector, .. } => {
+            output.push_str(&format!("<selector:{}>", quote(selector)));
+        }
+        Content::Nbt { .. } => write_escaped(&component.content.to_string(), output),
+    }
+
+    for child in &component.extra {
+        write_component(child, output);
+    }
+
+    for (_, close) in tags.iter().rev() {
+        output.push_str(close);
+    }
+}
+
+/// The opening and closing tags that express a style.
+fn style_tags(style: &Style) -> Vec<(String, String)> {
+    let mut tags = Vec::new();
+    let mut push = |name: &str, arguments: Vec<String>| {
+        let mut open = format!("<{name}");
+        for argument in arguments {
+            open.push(':');
+            open.push_str(&argument);
+        }
+        open.push('>');
+        tags.push((open, format!("</{name}>")));
+    };
+
+    if let Some(color) = style.color {
+        match color {
+            TextColor::Named(color) => push(color.name(), vec![]),
+            TextColor::Rgb(value) => push(&format!("#{value:06x}"), vec![]),
+        }
+    }
+
+    let decorations = [
+        ("bold", style.bold),
+        ("italic", style.italic),
+        ("underlined", style.underlined),
+        ("strikethrough", style.strikethrough),
+        ("obfuscated", style.obfuscated),
+    ];
+    for (name, value) in decorations {
+        match value {
+            Some(true) => push(name, vec![]),
+            Some(false) => push(&format!("!{name}"), vec![]),
+            None => {}
+        }
+    }
+
+    if let Some(font) = &style.font {
+        push("font", vec![quote(&font.to_string())]);
+    }
+    if let Some(insertion) = &style.insertion {
+        push("insert", vec![quote(insertion)]);
+    }
+
+    let click = match &style.click_event {
+        Some(ClickEvent::OpenUrl { url }) => Some(("open_url", url.clone())),
+        Some(ClickEvent::OpenFile { path }) => Some(("open_file", path.clone())),
+        Some(ClickEvent::RunCommand { command }) => Some(("run_command", command.clone())),
+        Some(ClickEvent::SuggestCommand { command }) => Some(("suggest_command", command.clone())),
+        Some(ClickEvent::CopyToClipboard { value }) => Some(("copy_to_clipboard", value.clone())),
+        Some(ClickEvent::ChangePage { page }) => Some(("change_page", page.to_string())),
+        Some(ClickEvent::Custom { .. }) | None => None,
+    };
+    if let Some((action, value)) = click {
+        push("click", vec![action.to_string(), quote(&value)]);
+    }
+
+    if let Some(HoverEvent::ShowText { value }) = &style.hover_event {
+        push("hover", vec!["show_text".to_string(), quote(&serialize(value))]);
+    }
+
+    tags
+}
+
+fn write_escaped(text: &str, output: &mut String) {
+    for char in text.chars() {
+        if char == '<' || char == '\\' {
+            output.push('\\');
+        }
+        output.push(char);
+    }
+}
+
+fn quote(argument: &str) -> String {
+    format!("'{}'", argument.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mc::text::color::NamedColor;
+
+    fn assert_round_trip(component: &TextComponent) {
+        let markup = serialize(component);
+        assert_eq!(parse(&markup).as_ref(), Ok(component), "markup: {markup}");
+    }
+
+    fn error_at(input: &str) -> (usize, String) {
+        let err = parse(input).expect_err("markup should be invalid");
+        (err.position(), err.message().to_string())
+    }
+
+    #[test]
+    fn round_trips_styled_text() {
+        assert_round_trip(&TextComponent::text("Hello").with_color(NamedColor::Red));
+        assert_round_trip(&TextComponent::text("#1").with_color(TextColor::rgb(0x12, 0xab, 0xef)));
+        assert_round_trip(&TextComponent::text("a < b \\ c"));
+
+        let style = Style {
+            bold: Some(true),
+            italic: Some(false),
+            font: Some("minecraft:uniform".parse().unwrap()),
+            insertion: Some("it's".to_string()),
+            click_event: Some(ClickEvent::RunCommand { command: "/say a:b>c".to_string() }),
+            hover_event: Some(HoverEvent::show_text(
+                TextComponent::text("hi").with_color(NamedColor::Gold),
+            )),
+            ..Style::default()
+        };
+        assert_round_trip(&TextComponent::text("click").with_style(style));
+    }
+
+    #[test]
+    fn round_trips_parsed_markup() {
+        let inputs = [
+            "<red>Hello <bold>world</bold>!",
+            "<lang:'chat.type.text':'<yellow>Steve':'hi'> <key:key.jump>",
+            "<score:'@p':kills> <selector:'@a[distance=..5]'>",
+            "<click:change_page:3><u>next</u></click>",
+        ];
+        for input in inputs {
+            assert_round_trip(&parse(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn keeps_lone_angle_brackets_as_text() {
+        assert_eq!(parse("a < b"), Ok(TextComponent::text("a < b")));
+        assert_eq!(parse("a <"), Ok(TextComponent::text("a <")));
+        assert_eq!(parse("1 <2"), Ok(TextComponent::text("1 <2")));
+
+        let component = parse("<red>x < y").unwrap();
+        assert_eq!(component, TextComponent::text("x < y").with_color(NamedColor::Red));
+    }
+
+    #[test]
+    fn keeps_unknown_tags_as_text() {
+        assert_eq!(parse("1<2 and 3>2"), Ok(TextComponent::text("1<2 and 3>2")));
+        assert_eq!(parse("<bogus>text</bogus>"), Ok(TextComponent::text("<bogus>text</bogus>")));
+
+        let component = parse("<red>Hello</blue> <!nope>world").unwrap();
+        assert_eq!(
+            component,
+            TextComponent::text("Hello</blue> <!nope>world").with_color(NamedColor::Red)
+        );
+    }
+
+    #[test]
+    fn colors_text_with_gradients() {
+        let component = parse("<gradient:#ff0000:#0000ff>abc</gradient>").unwrap();
+        assert_eq!(
+            component,
+            TextComponent::text("")
+                .append(TextComponent::text("a").with_color(TextColor::rgb(0xff, 0x00, 0x00)))
+                .append(TextComponent::text("b").with_color(TextColor::rgb(0x80, 0x00, 0x80)))
+                .append(TextComponent::text("c").with_color(TextColor::rgb(0x00, 0x00, 0xff)))
+        );
+    }
+
+    #[test]
+    fn skips_colored_children_in_gradients() {
+        let component = parse("<gradient:#000000:#ffffff:#000000>a<green>bb</green>cd</gradient>");
+
+        let character =
+            |char, value| TextComponent::text(char).with_color(TextColor::rgb(value, value, value));
+        assert_eq!(
+            component,
+            Ok(TextComponent::text("")
+                .append(TextComponent::text("").append(character("a", 0x00)))
+                .append(TextComponent::text("bb").with_color(NamedColor::Green))
+                .append(
+                    TextComponent::text("")
+                        .append(character("c", 0xff))
+                        .append(character("d", 0x00))
+                ))
+        );
+    }
+
+    #[test]
+    fn reports_error_positions() {
+        assert_eq!(error_at("ab<bold:maybe>"), (8, "expected 'true' or 'false'".into()));
+        assert_eq!(
+            error_at("<click:open_url:'https://"),
+            (16, "unterminated quoted argument".into())
+        );
+        assert_eq!(error_at("<color:nope>"), (7, "invalid color 'nope'".into()));
+        assert_eq!(error_at("<gradient:red>"), (1, "gradient needs at least two colors".into()));
+    }
+
+    #[test]
+    fn reports_error_positions_inside_arguments() {
+        assert_eq!(
+            error_at("<hover:show_text:'a <bold:maybe>'>"),
+            (26, "expected 'true' or 'false'".into())
+        );
+        assert_eq!(error_at("<lang:key:'<color:nope>'>"), (18, "invalid color 'nope'".into()));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod ansi;
+pub mod color;
+pub mod event;
+pub mod language;
+pub mod legacy;
+pub mod markup;
 pub mod style;
 pub mod text_component;
@@ -1,23 +1,125 @@
-// TODO: This is very ad-hoc. Properly serialize fields with enums etc.
-//       https://minecraft.wiki/w/Text_component_format#Java_Edition
-#[derive(Debug, Clone, Default)]
-#[derive(serde::Serialize, serde::Deserialize)]
+use serde::ser::SerializeStruct;
+
+use crate::mc::text::color::TextColor;
+use crate::mc::text::event::{ClickEvent, HoverEvent};
+use crate::mc::types::Identifier;
+
+/// The formatting of a text component. Properties that are not set are inherited from the parent
+/// component.
+///
+/// https://minecraft.wiki/w/Text_component_format#Java_Edition
+#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(serde::Deserialize)]
 #[serde(default)]
 pub struct Style {
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub color: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub font: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<TextColor>,
+    /// The color of the text shadow, as `0xAARRGGBB`.
+    pub shadow_color: Option<i32>,
+    pub font: Option<Identifier>,
     pub bold: Option<bool>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub italic: Option<bool>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub underlined: Option<bool>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub strikethrough: Option<bool>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub obfuscated: Option<bool>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub shadow_color: Option<String>,
+    /// Text inserted into the chat input when the player shift-clicks on the text.
+    pub insertion: Option<String>,
+    pub click_event: Option<ClickEvent>,
+    pub hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns this style with the properties that are not set taken from `parent`, like the
+    /// client does when rendering child components.
+    pub fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            color: self.color.or(parent.color),
+            shadow_color: self.shadow_color.or(parent.shadow_color),
+            font: self.font.clone().or_else(|| parent.font.clone()),
+            bold: self.bold.or(parent.bold),
+            italic: self.italic.or(parent.italic),
+            underlined: self.underlined.or(parent.underlined),
+            strikethrough: self.strikethrough.or(parent.strikethrough),
+            obfuscated: self.obfuscated.or(parent.obfuscated),
+            insertion: self.insertion.clone().or_else(|| parent.insertion.clone()),
+            click_event: self.click_event.clone().or_else(|| parent.click_event.clone()),
+            hover_event: self.hover_event.clone().or_else(|| parent.hover_event.clone()),
+        }
+    }
+
+    pub(crate) fn field_count(&self) -> usize {
+        [
+            self.color.is_some(),
+            self.shadow_color.is_some(),
+            self.font.is_some(),
+            self.bold.is_some(),
+            self.italic.is_some(),
+            self.underlined.is_some(),
+            self.strikethrough.is_some(),
+            self.obfuscated.is_some(),
+            self.insertion.is_some(),
+            self.click_event.is_some(),
+            self.hover_event.is_some(),
+        ]
+        .into_iter()
+        .filter(|is_set| *is_set)
+        .count()
+    }
+
+    /// Serializes the properties that are set as fields of the surrounding struct, because text
+    /// components store their style inline.
+    pub(crate) fn serialize_fields<S: SerializeStruct>(
+        &self,
+        state: &mut S,
+    ) -> Result<(), S::Error> {
+        if let Some(color) = &self.color {
+            state.serialize_field("color", color)?;
+        }
+        if let Some(shadow_color) = &self.shadow_color {
+            state.serialize_field("shadow_color", shadow_color)?;
+        }
+        if let Some(font) = &self.font {
+            state.serialize_field("font", font)?;
+        }
+        if let Some(bold) = &self.bold {
+            state.serialize_field("bold", bold)?;
+        }
+        if let Some(italic) = &self.italic {
+            state.serialize_field("italic", italic)?;
+        }
+        if let Some(underlined) = &self.underlined {
+            state.serialize_field("underlined", underlined)?;
+        }
+        if let Some(strikethrough) = &self.strikethrough {
+            state.serialize_field("strikethrough", strikethrough)?;
+        }
+        if let Some(obfuscated) = &self.obfuscated {
+            state.serialize_field("obfuscated", obfuscated)?;
+        }
+        if let Some(insertion) = &self.insertion {
+            state.serialize_field("insertion", insertion)?;
+        }
+        if let Some(click_event) = &self.click_event {
+            state.serialize_field("click_event", click_event)?;
+        }
+        if let Some(hover_event) = &self.hover_event {
+            state.serialize_field("hover_event", hover_event)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl serde::Serialize for Style {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Style", self.field_count())?;
+        self.serialize_fields(&mut state)?;
+        state.end()
+    }
 }
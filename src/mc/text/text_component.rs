@@ -1,25 +1,514 @@
 use std::fmt;
 
-// TODO: Properly implement.
-#[derive(Debug, Clone)]
-#[derive(serde::Serialize, serde::Deserialize)]
+use serde::Deserialize;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeStruct;
+
+use crate::mc::text::color::TextColor;
+use crate::mc::text::style::Style;
+use crate::mc::types::Identifier;
+
+/// Rich text, sent as JSON in the login and status states and as NBT in the configuration and
+/// play states.
+///
+/// https://minecraft.wiki/w/Text_component_format#Java_Edition
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Deserialize)]
+#[serde(try_from = "RawTextComponent")]
 pub struct TextComponent {
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub text: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub translate: Option<String>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub color: Option<String>,
+    pub content: Content,
+    // Boxed to keep components small, as they are passed around in results and messages.
+    pub style: Box<Style>,
+    /// Components appended to this one, which inherit its style.
+    pub extra: Vec<TextComponent>,
+}
+
+/// What a text component displays.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Content {
+    Text(String),
+    /// Text translated by the client, with `%s` placeholders replaced by the arguments.
+    Translatable {
+        key: String,
+        /// Shown if the client has no translation for the key.
+        fallback: Option<String>,
+        with: Vec<TextComponent>,
+    },
+    /// The score of an entity in a scoreboard objective.
+    Score {
+        name: String,
+        objective: String,
+    },
+    /// The names of the entities matched by an entity selector.
+    Selector {
+        selector: String,
+        separator: Option<Box<TextComponent>>,
+    },
+    /// The key bound to a control, e.g. `key.jump`.
+    Keybind(String),
+    /// NBT values read from a block entity, entities or command storage.
+    Nbt {
+        path: String,
+        interpret: Option<bool>,
+        separator: Option<Box<TextComponent>>,
+        source: NbtSource,
+    },
+}
+
+/// Where the values of an NBT text component are read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NbtSource {
+    /// The coordinates of a block entity.
+    Block(String),
+    /// An entity selector.
+    Entity(String),
+    Storage(Identifier),
+}
+
+impl TextComponent {
+    pub fn new(content: Content) -> Self {
+        Self { content, style: Box::default(), extra: Vec::new() }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(Content::Text(text.into()))
+    }
+
+    pub fn translatable(key: impl Into<String>, with: Vec<TextComponent>) -> Self {
+        Self::new(Content::Translatable { key: key.into(), fallback: None, with })
+    }
+
+    pub fn keybind(key: impl Into<String>) -> Self {
+        Self::new(Content::Keybind(key.into()))
+    }
+
+    pub fn score(name: impl Into<String>, objective: impl Into<String>) -> Self {
+        Self::new(Content::Score { name: name.into(), objective: objective.into() })
+    }
+
+    pub fn selector(selector: impl Into<String>) -> Self {
+        Self::new(Content::Selector { selector: selector.into(), separator: None })
+    }
+
+    pub fn with_style(mut self, style: Style) -> Self {
+        self.style = Box::new(style);
+        self
+    }
+
+    pub fn with_color(mut self, color: impl Into<TextColor>) -> Self {
+        self.style.color = Some(color.into());
+        self
+    }
+
+    /// Appends a child component, which inherits the style of this component.
+    pub fn append(mut self, child: TextComponent) -> Self {
+        self.extra.push(child);
+        self
+    }
+}
+
+impl From<&str> for TextComponent {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
 }
 
+impl From<String> for TextComponent {
+    fn from(text: String) -> Self {
+        Self::text(text)
+    }
+}
+
+/// Writes the text without formatting. Translatable and keybind components are written as their
+/// fallback or key, use [AnsiRenderer](crate::mc::text::ansi::AnsiRenderer) to resolve them.
 impl fmt::Display for TextComponent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(text) = &self.text {
-            write!(f, "{}", text)
-        } else if let Some(translate) = &self.translate {
-            write!(f, "{}", translate)
+        write!(f, "{}", self.content)?;
+
+        for child in &self.extra {
+            write!(f, "{child}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the content of the component alone, without its extra components.
+impl fmt::Display for Content {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Translatable { key, fallback, .. } => {
+                f.write_str(fallback.as_deref().unwrap_or(key))
+            }
+            Self::Score { .. } => Ok(()),
+            Self::Selector { selector, .. } => f.write_str(selector),
+            Self::Keybind(key) => f.write_str(key),
+            Self::Nbt { path, .. } => f.write_str(path),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ScoreValue {
+    name: String,
+    objective: String,
+}
+
+impl serde::Serialize for TextComponent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let content_field_count = match &self.content {
+            Content::Text(_) | Content::Score { .. } | Content::Keybind(_) => 1,
+            Content::Translatable { fallback, with, .. } => {
+                1 + fallback.is_some() as usize + !with.is_empty() as usize
+            }
+            Content::Selector { separator, .. } => 1 + separator.is_some() as usize,
+            Content::Nbt { interpret, separator, .. } => {
+                2 + interpret.is_some() as usize + separator.is_some() as usize
+            }
+        };
+        let field_count =
+            content_field_count + self.style.field_count() + !self.extra.is_empty() as usize;
+
+        let mut state = serializer.serialize_struct("TextComponent", field_count)?;
+
+        match &self.content {
+            Content::Text(text) => state.serialize_field("text", text)?,
+            Content::Translatable { key, fallback, with } => {
+                state.serialize_field("translate", key)?;
+                if let Some(fallback) = fallback {
+                    state.serialize_field("fallback", fallback)?;
+                }
+                if !with.is_empty() {
+                    state.serialize_field("with", with)?;
+                }
+            }
+            Content::Score { name, objective } => {
+                let score = ScoreValue { name: name.clone(), objective: objective.clone() };
+                state.serialize_field("score", &score)?;
+            }
+            Content::Selector { selector, separator } => {
+                state.serialize_field("selector", selector)?;
+                if let Some(separator) = separator {
+                    state.serialize_field("separator", separator)?;
+                }
+            }
+            Content::Keybind(key) => state.serialize_field("keybind", key)?,
+            Content::Nbt { path, interpret, separator, source } => {
+                state.serialize_field("nbt", path)?;
+                if let Some(interpret) = interpret {
+                    state.serialize_field("interpret", interpret)?;
+                }
+                if let Some(separator) = separator {
+                    state.serialize_field("separator", separator)?;
+                }
+                match source {
+                    NbtSource::Block(position) => state.serialize_field("block", position)?,
+                    NbtSource::Entity(selector) => state.serialize_field("entity", selector)?,
+                    NbtSource::Storage(storage) => state.serialize_field("storage", storage)?,
+                }
+            }
+        }
+
+        self.style.serialize_fields(&mut state)?;
+
+        if !self.extra.is_empty() {
+            state.serialize_field("extra", &self.extra)?;
+        }
+
+        state.end()
+    }
+}
+
+/// The shapes a text component can be written in. Besides objects, plain strings and numbers
+/// are text components, and arrays are a component followed by its extra components.
+enum RawTextComponent {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Array(Vec<TextComponent>),
+    Object(Box<RawTextObject>),
+}
+
+// Deserialized by hand rather than as an untagged enum, so that errors in nested components,
+// such as an invalid color in an extra component, are reported instead of "did not match any
+// variant".
+impl<'de> serde::Deserialize<'de> for RawTextComponent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RawTextComponentVisitor)
+    }
+}
+
+struct RawTextComponentVisitor;
+
+impl<'de> Visitor<'de> for RawTextComponentVisitor {
+    type Value = RawTextComponent;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a text component")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::String(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::Number(value as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::Number(value as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::Number(value))
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(RawTextComponent::Bool(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut components = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+        while let Some(component) = seq.next_element()? {
+            components.push(component);
+        }
+        Ok(RawTextComponent::Array(components))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        let object = RawTextObject::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        Ok(RawTextComponent::Object(Box::new(object)))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RawTextObject {
+    text: Option<String>,
+    translate: Option<String>,
+    fallback: Option<String>,
+    #[serde(default)]
+    with: Vec<TextComponent>,
+    score: Option<ScoreValue>,
+    selector: Option<String>,
+    separator: Option<Box<TextComponent>>,
+    keybind: Option<String>,
+    nbt: Option<String>,
+    interpret: Option<bool>,
+    block: Option<String>,
+    entity: Option<String>,
+    storage: Option<Identifier>,
+    #[serde(default)]
+    extra: Vec<TextComponent>,
+    #[serde(flatten)]
+    style: Style,
+}
+
+impl TryFrom<RawTextComponent> for TextComponent {
+    type Error = String;
+
+    fn try_from(raw: RawTextComponent) -> Result<Self, Self::Error> {
+        let object = match raw {
+            RawTextComponent::String(text) => return Ok(Self::text(text)),
+            RawTextComponent::Number(number) => return Ok(Self::text(number.to_string())),
+            RawTextComponent::Bool(value) => return Ok(Self::text(value.to_string())),
+            RawTextComponent::Array(components) => {
+                let mut components = components.into_iter();
+                let mut component = components.next().ok_or("empty text component array")?;
+                component.extra.extend(components);
+                return Ok(component);
+            }
+            RawTextComponent::Object(object) => *object,
+        };
+
+        // Like the client, the first content type that is present wins.
+        let content = if let Some(text) = object.text {
+            Content::Text(text)
+        } else if let Some(key) = object.translate {
+            Content::Translatable { key, fallback: object.fallback, with: object.with }
+        } else if let Some(score) = object.score {
+            Content::Score { name: score.name, objective: score.objective }
+        } else if let Some(selector) = object.selector {
+            Content::Selector { selector, separator: object.separator }
+        } else if let Some(key) = object.keybind {
+            Content::Keybind(key)
+        } else if let Some(path) = object.nbt {
+            let source = if let Some(position) = object.block {
+                NbtSource::Block(position)
+            } else if let Some(selector) = object.entity {
+                NbtSource::Entity(selector)
+            } else if let Some(storage) = object.storage {
+                NbtSource::Storage(storage)
+            } else {
+                return Err("nbt text component without a source".to_string());
+            };
+            Content::Nbt { path, interpret: object.interpret, separator: object.separator, source }
         } else {
-            write!(f, "")
+            return Err("text component without content".to_string());
+        };
+
+        Ok(Self { content, style: Box::new(object.style), extra: object.extra })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::mc::nbt::{self, WriteMode};
+    use crate::mc::text::color::NamedColor;
+    use crate::mc::text::event::{ClickEvent, HoverEvent};
+
+    fn round_trip(component: TextComponent) {
+        let json = serde_json::to_string(&component).unwrap();
+        let parsed: TextComponent = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, component, "{json}");
+    }
+
+    fn separator() -> Option<Box<TextComponent>> {
+        Some(Box::new(TextComponent::text(", ").with_color(NamedColor::Gray)))
+    }
+
+    #[test]
+    fn round_trips_every_content_type() {
+        round_trip(TextComponent::text("Hello").append(TextComponent::text(" world")));
+        round_trip(TextComponent::new(Content::Translatable {
+            key: "chat.type.text".to_string(),
+            fallback: Some("<%s> %s".to_string()),
+            with: vec![TextComponent::text("Steve"), TextComponent::text("hi")],
+        }));
+        round_trip(TextComponent::score("@s", "kills"));
+        round_trip(TextComponent::new(Content::Selector {
+            selector: "@a".to_string(),
+            separator: separator(),
+        }));
+        round_trip(TextComponent::keybind("key.jump"));
+        round_trip(TextComponent::new(Content::Nbt {
+            path: "Items[0].id".to_string(),
+            interpret: Some(false),
+            separator: separator(),
+            source: NbtSource::Block("~ ~-1 ~".to_string()),
+        }));
+        round_trip(TextComponent::new(Content::Nbt {
+            path: "Health".to_string(),
+            interpret: None,
+            separator: None,
+            source: NbtSource::Entity("@p".to_string()),
+        }));
+        round_trip(TextComponent::new(Content::Nbt {
+            path: "counter".to_string(),
+            interpret: Some(true),
+            separator: None,
+            source: NbtSource::Storage(Identifier::new("keisteen", "data").unwrap()),
+        }));
+    }
+
+    #[test]
+    fn round_trips_events() {
+        let clicks = [
+            ClickEvent::OpenUrl { url: "https://example.com".to_string() },
+            ClickEvent::OpenFile { path: "screenshots".to_string() },
+            ClickEvent::RunCommand { command: "/help".to_string() },
+            ClickEvent::SuggestCommand { command: "/msg ".to_string() },
+            ClickEvent::ChangePage { page: 2 },
+            ClickEvent::CopyToClipboard { value: "copied".to_string() },
+            ClickEvent::Custom { id: Identifier::new("keisteen", "click").unwrap(), payload: None },
+        ];
+        for click_event in clicks {
+            let style = Style { click_event: Some(click_event), ..Style::default() };
+            round_trip(TextComponent::text("click").with_style(style));
         }
+
+        let hovers = [
+            HoverEvent::show_text(TextComponent::text("tooltip").with_color(NamedColor::Gold)),
+            HoverEvent::ShowItem {
+                id: Identifier::new("minecraft", "stone").unwrap(),
+                count: None,
+            },
+            HoverEvent::ShowEntity {
+                id: Identifier::new("minecraft", "pig").unwrap(),
+                uuid: Uuid::new_v4(),
+                name: Some(Box::new(TextComponent::text("Pig"))),
+            },
+        ];
+        for hover_event in hovers {
+            let style = Style { hover_event: Some(hover_event), ..Style::default() };
+            round_trip(TextComponent::text("hover").with_style(style));
+        }
+    }
+
+    #[test]
+    fn writes_events_as_objects_with_an_action() {
+        let style = Style {
+            click_event: Some(ClickEvent::RunCommand { command: "/spawn".to_string() }),
+            ..Style::default()
+        };
+        let json = serde_json::to_value(TextComponent::text("go").with_style(style)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "text": "go",
+                "click_event": { "action": "run_command", "command": "/spawn" },
+            })
+        );
+    }
+
+    #[test]
+    fn reads_shorthand_components() {
+        let parse = |json| serde_json::from_str::<TextComponent>(json).unwrap();
+
+        assert_eq!(parse(r#""plain""#), TextComponent::text("plain"));
+        assert_eq!(parse("12"), TextComponent::text("12"));
+        assert_eq!(parse("true"), TextComponent::text("true"));
+        assert_eq!(
+            parse(r#"["a", {"text": "b", "bold": true}]"#),
+            TextComponent::text("a").append(
+                TextComponent::text("b").with_style(Style { bold: Some(true), ..Style::default() })
+            )
+        );
+    }
+
+    #[test]
+    fn reports_errors_in_nested_components() {
+        let json = r##"{"text": "a", "extra": [{"text": "b", "color": "#ff00zz"}]}"##;
+        let err = serde_json::from_str::<TextComponent>(json).unwrap_err();
+        assert!(err.to_string().contains("invalid hex color: #ff00zz"), "{err}");
+
+        let err = serde_json::from_str::<TextComponent>(r#"["a", {"color": "red"}]"#).unwrap_err();
+        assert!(err.to_string().contains("text component without content"), "{err}");
+    }
+
+    #[test]
+    fn writes_nbt_for_the_configuration_and_play_states() {
+        let component = TextComponent::text("Hi")
+            .with_style(Style { bold: Some(true), ..Style::default() })
+            .append(TextComponent::text("!"));
+
+        let mut bytes = Vec::new();
+        nbt::to_value(&component).unwrap().to_writer(&mut bytes, WriteMode::Network).unwrap();
+
+        // Network NBT leaves out the name of the root compound.
+        #[rustfmt::skip]
+        let expected = [
+            10,
+                8, 0, 4, b't', b'e', b'x', b't', 0, 2, b'H', b'i',
+                1, 0, 4, b'b', b'o', b'l', b'd', 1,
+                9, 0, 5, b'e', b'x', b't', b'r', b'a', 10, 0, 0, 0, 1,
+                    8, 0, 4, b't', b'e', b'x', b't', 0, 1, b'!',
+                    0,
+            0,
+        ];
+        assert_eq!(bytes, expected);
     }
 }
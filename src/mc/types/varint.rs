@@ -43,7 +43,7 @@ impl VarInt {
 
         loop {
             let mut byte_buf = [0];
-            reader.read(&mut byte_buf)?;
+            reader.read_exact(&mut byte_buf)?;
             current_byte = byte_buf[0];
 
             value |= ((current_byte & Self::SEGMENT_BITS) as i32) << position;
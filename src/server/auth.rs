@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use eyre::Context;
+use uuid::Uuid;
+
+use crate::error::KeisteenResult;
+use crate::server::player_profile::{PlayerProfile, ProfileProperty};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A service that verifies that a player has joined the server through their Minecraft client.
+pub trait SessionService: Send + Sync {
+    /// Checks whether the player with the given username has joined the server identified by
+    /// `server_hash`. Returns the authenticated profile, or `None` if the player could not be
+    /// verified.
+    fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+    ) -> KeisteenResult<Option<PlayerProfile>>;
+}
+
+/// A [SessionService] that talks to a Mojang compatible session server over HTTP.
+#[derive(Debug, Clone)]
+pub struct HttpSessionService {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpSessionService {
+    pub const MOJANG_URL: &str = "https://sessionserver.mojang.com";
+
+    pub fn new(base_url: impl Into<String>) -> Self {
+        // Each login waits for the session service on its own thread, so an unresponsive service
+        // must not keep those threads around forever.
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout_read(READ_TIMEOUT)
+            .build();
+        Self { base_url: base_url.into(), agent }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+}
+
+impl SessionService for HttpSessionService {
+    fn has_joined(
+        &self,
+        username: &str,
+        server_hash: &str,
+    ) -> KeisteenResult<Option<PlayerProfile>> {
+        let url = format!("{}/session/minecraft/hasJoined", self.base_url.trim_end_matches('/'));
+
+        let response = self
+            .agent
+            .get(&url)
+            .query("username", username)
+            .query("serverId", server_hash)
+            .call()
+            .wrap_err("failed to reach session service")?;
+
+        if response.status() != 200 {
+            return Ok(None);
+        }
+
+        let body = response.into_string().wrap_err("failed to read session service response")?;
+        let HasJoinedResponse { id, name, properties } =
+            serde_json::from_str(&body).wrap_err("failed to parse session service response")?;
+
+        Ok(Some(PlayerProfile::new(id, name).with_properties(properties)))
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct HasJoinedResponse {
+    id: Uuid,
+    name: String,
+    #[serde(default)]
+    properties: Vec<ProfileProperty>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::server::folder::ServerConfig;
+
+    /// Starts a session server that answers a single request, and returns its URL along with
+    /// the request line it received.
+    fn stub_session_server(status: &str, body: &str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+
+            stream.write_all(response.as_bytes()).unwrap();
+            request_line
+        });
+
+        (url, handle)
+    }
+
+    fn config_with_session_service(url: &str) -> ServerConfig {
+        toml::from_str(&format!("session_service_url = \"{url}\"")).unwrap()
+    }
+
+    #[test]
+    fn authenticates_against_configured_session_service() {
+        let body = r#"{
+            "id": "069a79f444e94726a5befca90e38aaf5",
+            "name": "Notch",
+            "properties": [{ "name": "textures", "value": "dGV4dHVyZXM=", "signature": "c2ln" }]
+        }"#;
+        let (url, server) = stub_session_server("200 OK", body);
+
+        let config = config_with_session_service(&url);
+        let service = HttpSessionService::new(config.session_service_url());
+        let profile = service.has_joined("Notch", "-7c9d5b").unwrap().expect("should be verified");
+
+        assert_eq!(profile.username(), "Notch");
+        assert_eq!(
+            profile.uuid(),
+            Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()
+        );
+        assert_eq!(profile.properties().len(), 1);
+
+        let request_line = server.join().unwrap();
+        assert!(request_line.starts_with("GET /session/minecraft/hasJoined?"));
+        assert!(request_line.contains("username=Notch"));
+        assert!(request_line.contains("serverId=-7c9d5b"));
+    }
+
+    #[test]
+    fn rejects_players_the_session_service_does_not_know() {
+        let (url, server) = stub_session_server("204 No Content", "");
+
+        let service =
+            HttpSessionService::new(config_with_session_service(&url).session_service_url());
+        assert!(service.has_joined("Notch", "abc").unwrap().is_none());
+
+        server.join().unwrap();
+    }
+}
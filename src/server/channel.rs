@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::KeisteenResult;
+use crate::mc::types::Identifier;
+use crate::server::conn::Connection;
+
+/// Handles a plugin message received from a client on the network thread.
+pub type PluginMessageHandler =
+    Arc<dyn Fn(&mut Connection, &[u8]) -> KeisteenResult<()> + Send + Sync>;
+
+/// The plugin message channels the server listens on.
+///
+/// Registered channels are announced to clients with `minecraft:register` when they enter the
+/// configuration state.
+#[derive(Default)]
+pub struct ChannelRegistry {
+    handlers: HashMap<Identifier, PluginMessageHandler>,
+}
+
+impl ChannelRegistry {
+    /// Registers `handler` for `channel`, replacing any handler registered before.
+    pub fn register<F>(&mut self, channel: Identifier, handler: F)
+    where
+        F: Fn(&mut Connection, &[u8]) -> KeisteenResult<()> + Send + Sync + 'static,
+    {
+        self.handlers.insert(channel, Arc::new(handler));
+    }
+
+    /// Removes the handler for `channel`. Returns `false` if none was registered.
+    pub fn unregister(&mut self, channel: &Identifier) -> bool {
+        self.handlers.remove(channel).is_some()
+    }
+
+    pub fn is_registered(&self, channel: &Identifier) -> bool {
+        self.handlers.contains_key(channel)
+    }
+
+    pub fn channels(&self) -> impl Iterator<Item = &Identifier> {
+        self.handlers.keys()
+    }
+
+    pub fn handler(&self, channel: &Identifier) -> Option<PluginMessageHandler> {
+        self.handlers.get(channel).cloned()
+    }
+}
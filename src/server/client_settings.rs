@@ -0,0 +1,210 @@
+use std::io;
+use std::ops::BitOr;
+
+use eyre::bail;
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::ProtocolRead;
+use crate::mc::types::VarInt;
+use crate::server::translations::DEFAULT_LOCALE;
+
+/// The settings a client sends in the client information packet, during configuration and
+/// whenever the player changes them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSettings {
+    /// The language the client is set to, e.g. `en_us`.
+    pub locale: String,
+    /// The render distance of the client, in chunks.
+    pub view_distance: i8,
+    pub chat_mode: ChatMode,
+    pub chat_colors: bool,
+    pub displayed_skin_parts: DisplayedSkinParts,
+    pub main_hand: MainHand,
+    pub enable_text_filtering: bool,
+    /// Whether the player may be shown in the player sample of the server list.
+    pub allow_server_listing: bool,
+    pub particle_status: ParticleStatus,
+}
+
+impl Default for ClientSettings {
+    /// The settings vanilla assumes before the client has sent its own.
+    fn default() -> Self {
+        Self {
+            locale: DEFAULT_LOCALE.to_string(),
+            view_distance: 2,
+            chat_mode: ChatMode::Enabled,
+            chat_colors: true,
+            displayed_skin_parts: DisplayedSkinParts::empty(),
+            main_hand: MainHand::Right,
+            enable_text_filtering: false,
+            allow_server_listing: false,
+            particle_status: ParticleStatus::All,
+        }
+    }
+}
+
+impl ProtocolRead for ClientSettings {
+    fn read_from<R: io::Read>(reader: &mut R) -> KeisteenResult<Self> {
+        Ok(Self {
+            locale: String::read_from(reader)?.to_lowercase(),
+            view_distance: i8::read_from(reader)?,
+            chat_mode: match VarInt::read_from(reader)?.raw() {
+                0 => ChatMode::Enabled,
+                1 => ChatMode::CommandsOnly,
+                2 => ChatMode::Hidden,
+                chat_mode => bail!("invalid chat mode: {chat_mode}"),
+            },
+            chat_colors: bool::read_from(reader)?,
+            displayed_skin_parts: DisplayedSkinParts::from_bits(u8::read_from(reader)?),
+            main_hand: match VarInt::read_from(reader)?.raw() {
+                0 => MainHand::Left,
+                1 => MainHand::Right,
+                main_hand => bail!("invalid main hand: {main_hand}"),
+            },
+            enable_text_filtering: bool::read_from(reader)?,
+            allow_server_listing: bool::read_from(reader)?,
+            particle_status: match VarInt::read_from(reader)?.raw() {
+                0 => ParticleStatus::All,
+                1 => ParticleStatus::Decreased,
+                2 => ParticleStatus::Minimal,
+                particle_status => bail!("invalid particle status: {particle_status}"),
+            },
+        })
+    }
+}
+
+/// Which chat messages the client wants to receive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatMode {
+    Enabled,
+    /// Only the results of commands.
+    CommandsOnly,
+    Hidden,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MainHand {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleStatus {
+    All,
+    Decreased,
+    Minimal,
+}
+
+/// The layers of the skin the player has enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DisplayedSkinParts(u8);
+
+impl DisplayedSkinParts {
+    pub const CAPE: Self = Self(0x01);
+    pub const JACKET: Self = Self(0x02);
+    pub const LEFT_SLEEVE: Self = Self(0x04);
+    pub const RIGHT_SLEEVE: Self = Self(0x08);
+    pub const LEFT_PANTS_LEG: Self = Self(0x10);
+    pub const RIGHT_PANTS_LEG: Self = Self(0x20);
+    pub const HAT: Self = Self(0x40);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn all() -> Self {
+        Self(0x7F)
+    }
+
+    /// Creates the set from its bits, ignoring bits that do not stand for a skin part.
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & Self::all().0)
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn contains(&self, parts: Self) -> bool {
+        self.0 & parts.0 == parts.0
+    }
+
+    pub fn insert(&mut self, parts: Self) {
+        self.0 |= parts.0;
+    }
+
+    pub fn remove(&mut self, parts: Self) {
+        self.0 &= !parts.0;
+    }
+}
+
+impl BitOr for DisplayedSkinParts {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The client information packet of a German client with the given enum values, as
+    /// `(chat_mode, main_hand, particle_status)`.
+    fn encode(enums: (u8, u8, u8), skin_parts: u8) -> Vec<u8> {
+        let (chat_mode, main_hand, particle_status) = enums;
+        let mut data = vec![5];
+        data.extend_from_slice(b"de_DE");
+        data.extend_from_slice(&[12, chat_mode, 0, skin_parts, main_hand, 1, 0, particle_status]);
+        data
+    }
+
+    #[test]
+    fn reads_client_settings() {
+        let settings = ClientSettings::read_from(&mut &encode((1, 0, 2), 0x41)[..]).unwrap();
+        assert_eq!(
+            settings,
+            ClientSettings {
+                locale: "de_de".to_string(),
+                view_distance: 12,
+                chat_mode: ChatMode::CommandsOnly,
+                chat_colors: false,
+                displayed_skin_parts: DisplayedSkinParts::CAPE | DisplayedSkinParts::HAT,
+                main_hand: MainHand::Left,
+                enable_text_filtering: true,
+                allow_server_listing: false,
+                particle_status: ParticleStatus::Minimal,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_enum_values() {
+        for enums in [(3, 1, 0), (0, 2, 0), (0, 1, 3)] {
+            assert!(ClientSettings::read_from(&mut &encode(enums, 0)[..]).is_err(), "{enums:?}");
+        }
+    }
+
+    #[test]
+    fn ignores_unknown_skin_part_bits() {
+        let parts = DisplayedSkinParts::from_bits(0xFF);
+        assert_eq!(parts, DisplayedSkinParts::all());
+        assert_eq!(parts.bits(), 0x7F);
+
+        let settings = ClientSettings::read_from(&mut &encode((0, 1, 0), 0x80)[..]).unwrap();
+        assert_eq!(settings.displayed_skin_parts, DisplayedSkinParts::empty());
+    }
+
+    #[test]
+    fn combines_skin_parts() {
+        let mut parts = DisplayedSkinParts::JACKET | DisplayedSkinParts::LEFT_SLEEVE;
+        assert!(parts.contains(DisplayedSkinParts::JACKET));
+        assert!(!parts.contains(DisplayedSkinParts::JACKET | DisplayedSkinParts::HAT));
+
+        parts.insert(DisplayedSkinParts::HAT);
+        parts.remove(DisplayedSkinParts::JACKET);
+        assert_eq!(parts.bits(), 0x44);
+        assert!(DisplayedSkinParts::all().contains(parts));
+    }
+}
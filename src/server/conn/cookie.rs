@@ -0,0 +1,143 @@
+use std::time::{Duration, Instant};
+
+use eyre::bail;
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::{PacketData, client};
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::Identifier;
+use crate::server::conn::{Connection, ConnectionState};
+
+/// The maximum size of a cookie payload, in bytes.
+pub const MAX_COOKIE_SIZE: usize = 5 * 1024;
+
+const COOKIE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieError {
+    /// The client did not answer the request in time.
+    TimedOut,
+}
+
+/// The payload of a requested cookie, or `None` if the client has no cookie stored for the key.
+pub type CookieResult = Result<Option<Vec<u8>>, CookieError>;
+
+type CookieCallback = Box<dyn FnOnce(&mut Connection, CookieResult) -> KeisteenResult<()> + Send>;
+
+/// Cookie requests that are waiting for a response from the client.
+#[derive(Default)]
+pub(crate) struct PendingCookies {
+    requests: Vec<PendingCookie>,
+}
+
+struct PendingCookie {
+    key: Identifier,
+    requested_at: Instant,
+    callback: CookieCallback,
+}
+
+impl PendingCookies {
+    fn take(&mut self, key: &Identifier) -> Option<CookieCallback> {
+        let index = self.requests.iter().position(|request| &request.key == key)?;
+        Some(self.requests.remove(index).callback)
+    }
+
+    fn take_expired(&mut self) -> Vec<CookieCallback> {
+        let (expired, pending) = std::mem::take(&mut self.requests)
+            .into_iter()
+            .partition(|request| request.requested_at.elapsed() > COOKIE_REQUEST_TIMEOUT);
+        self.requests = pending;
+
+        expired.into_iter().map(|request: PendingCookie| request.callback).collect()
+    }
+}
+
+impl Connection {
+    /// Asks the client for the cookie stored under `key`. The callback runs on the network thread
+    /// once the client answers, or with [CookieError::TimedOut] if it does not answer in time.
+    pub fn request_cookie<F>(&mut self, key: Identifier, callback: F) -> KeisteenResult<()>
+    where
+        F: FnOnce(&mut Connection, CookieResult) -> KeisteenResult<()> + Send + 'static,
+    {
+        let packet_key = key.clone();
+        match self.state {
+            ConnectionState::Login => {
+                self.send_packet(client::login::CookieRequest { key: packet_key })?
+            }
+            ConnectionState::Config => {
+                self.send_packet(client::config::CookieRequest { key: packet_key })?
+            }
+            ConnectionState::Play => {
+                self.send_packet(client::play::CookieRequest { key: packet_key })?
+            }
+            state => bail!("cannot request cookies in {state:?} state"),
+        }
+
+        self.pending_cookies.requests.push(PendingCookie {
+            key,
+            requested_at: Instant::now(),
+            callback: Box::new(callback),
+        });
+
+        Ok(())
+    }
+
+    /// Stores a cookie on the client, which it keeps until it disconnects, including across
+    /// transfers.
+    pub fn store_cookie(&mut self, key: Identifier, payload: Vec<u8>) -> KeisteenResult<()> {
+        if payload.len() > MAX_COOKIE_SIZE {
+            bail!("cookie payload of {} bytes exceeds {MAX_COOKIE_SIZE} bytes", payload.len());
+        }
+
+        match self.state {
+            ConnectionState::Config => {
+                self.send_packet(client::config::StoreCookie { key, payload })?
+            }
+            ConnectionState::Play => {
+                self.send_packet(client::play::StoreCookie { key, payload })?
+            }
+            state => bail!("cannot store cookies in {state:?} state"),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn receive_cookie(
+        &mut self,
+        key: Identifier,
+        payload: Option<Vec<u8>>,
+    ) -> KeisteenResult<()> {
+        let Some(callback) = self.pending_cookies.take(&key) else {
+            log::warn!("{} sent cookie '{key}' that was not requested", self.remote_address);
+            self.disconnect(Some(TextComponent::translatable(
+                "multiplayer.disconnect.unexpected_query_response",
+                vec![],
+            )));
+            return Ok(());
+        };
+
+        callback(self, Ok(payload))
+    }
+
+    pub(crate) fn expire_cookie_requests(&mut self) -> KeisteenResult<()> {
+        for callback in self.pending_cookies.take_expired() {
+            callback(self, Err(CookieError::TimedOut))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the optional payload of a cookie response, rejecting payloads above [MAX_COOKIE_SIZE].
+pub(crate) fn read_cookie_payload(data: &mut PacketData) -> KeisteenResult<Option<Vec<u8>>> {
+    if !data.read::<bool>()? {
+        return Ok(None);
+    }
+
+    let payload: Vec<u8> = data.read_prefixed()?;
+    if payload.len() > MAX_COOKIE_SIZE {
+        bail!("cookie payload of {} bytes exceeds {MAX_COOKIE_SIZE} bytes", payload.len());
+    }
+
+    Ok(Some(payload))
+}
@@ -0,0 +1,297 @@
+use std::net::{IpAddr, SocketAddr};
+
+use eyre::{Context, bail};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::PacketData;
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::{Identifier, VarInt};
+use crate::server::conn::Connection;
+use crate::server::conn::login_query::LoginQueryResult;
+use crate::server::player_profile::{PlayerProfile, ProfileProperty};
+
+// The version of Velocity's forwarding data that is requested. Later versions add the player's
+// chat signing key, which is not supported yet.
+const VELOCITY_FORWARDING_VERSION: u8 = 1;
+const VELOCITY_SIGNATURE_LENGTH: usize = 32;
+
+const BUNGEECORD_REJECT_MESSAGE: &str =
+    "If you wish to use IP forwarding, please enable it in your BungeeCord config as well!";
+
+/// Player information forwarded by a proxy.
+#[derive(Debug)]
+struct ForwardedPlayer {
+    address: IpAddr,
+    profile: PlayerProfile,
+}
+
+/// Player information forwarded by BungeeCord in the handshake. The username is only known once
+/// the client starts logging in.
+#[derive(Debug)]
+pub(crate) struct BungeeCordForwarding {
+    address: IpAddr,
+    uuid: Uuid,
+    properties: Vec<ProfileProperty>,
+}
+
+impl Connection {
+    /// Asks Velocity for the forwarded player information, which replaces the profile sent by the
+    /// client once it arrives.
+    pub(crate) fn request_velocity_forwarding(&mut self) -> KeisteenResult<()> {
+        self.send_login_plugin_request(
+            Identifier::new("velocity", "player_info")?,
+            vec![VELOCITY_FORWARDING_VERSION],
+            complete_velocity_forwarding,
+        )
+    }
+
+    /// Reads the player information BungeeCord appends to the server address of the handshake,
+    /// rejecting clients that connect without it.
+    pub(crate) fn accept_bungeecord_forwarding(
+        &mut self,
+        server_address: &str,
+    ) -> KeisteenResult<()> {
+        match parse_bungeecord_forwarding(server_address) {
+            Ok(forwarding) => {
+                self.bungeecord_forwarding = Some(forwarding);
+                Ok(())
+            }
+            Err(err) => {
+                log::warn!("rejecting forwarded player from {}: {err}", self.remote_address);
+                self.reject_forwarding(BUNGEECORD_REJECT_MESSAGE)
+            }
+        }
+    }
+
+    /// Completes the profile of a client whose information was forwarded by BungeeCord. Returns
+    /// `false` if no information was forwarded.
+    pub(crate) fn complete_bungeecord_forwarding(&mut self, username: String) -> bool {
+        let Some(forwarding) = self.bungeecord_forwarding.take() else {
+            return false;
+        };
+
+        self.apply_forwarded_player(ForwardedPlayer {
+            address: forwarding.address,
+            profile: PlayerProfile::new(forwarding.uuid, username)
+                .with_properties(forwarding.properties),
+        });
+
+        true
+    }
+
+    fn apply_forwarded_player(&mut self, player: ForwardedPlayer) {
+        log::info!(
+            "{} ({}) is connecting from {} through the proxy",
+            player.profile.username(),
+            player.profile.uuid(),
+            player.address
+        );
+
+        self.remote_address = SocketAddr::new(player.address, self.remote_address.port());
+        self.player_profile = Some(player.profile);
+    }
+
+    fn reject_forwarding(&mut self, reason: &str) -> KeisteenResult<()> {
+        self.disconnect(Some(TextComponent::text(reason)));
+
+        Ok(())
+    }
+}
+
+fn complete_velocity_forwarding(
+    conn: &mut Connection,
+    data: LoginQueryResult,
+) -> KeisteenResult<()> {
+    let Some(data) = data else {
+        log::warn!("{} connected without Velocity forwarding", conn.remote_address());
+        return conn.reject_forwarding("This server requires you to connect with Velocity.");
+    };
+
+    let secret = conn
+        .server()
+        .read(|server| server.server_folder().config().forwarding_secret().as_bytes().to_vec());
+
+    match parse_velocity_forwarding(&secret, data) {
+        Ok(player) => {
+            conn.apply_forwarded_player(player);
+            Ok(())
+        }
+        Err(err) => {
+            log::warn!("rejecting forwarded player from {}: {err}", conn.remote_address());
+            conn.reject_forwarding("Unable to verify player details.")
+        }
+    }
+}
+
+fn parse_velocity_forwarding(secret: &[u8], data: Vec<u8>) -> KeisteenResult<ForwardedPlayer> {
+    if data.len() < VELOCITY_SIGNATURE_LENGTH {
+        bail!("forwarding data is too short");
+    }
+
+    let (signature, forwarded) = data.split_at(VELOCITY_SIGNATURE_LENGTH);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).wrap_err("invalid forwarding secret")?;
+    mac.update(forwarded);
+    if mac.verify_slice(signature).is_err() {
+        bail!("forwarding signature does not match");
+    }
+
+    let mut forwarded = PacketData::from(forwarded);
+    let version = forwarded.read::<VarInt>()?.raw();
+    if version > VELOCITY_FORWARDING_VERSION as i32 {
+        bail!("unsupported forwarding version {version}");
+    }
+
+    let address: String = forwarded.read()?;
+    let uuid: Uuid = forwarded.read()?;
+    let username: String = forwarded.read()?;
+    let properties: Vec<ProfileProperty> = forwarded.read_prefixed()?;
+
+    Ok(ForwardedPlayer {
+        address: address.parse().wrap_err("invalid forwarded address")?,
+        profile: PlayerProfile::new(uuid, username).with_properties(properties),
+    })
+}
+
+/// Parses a server address of the form `host\0address\0uuid[\0properties]`, where the properties
+/// are a JSON array.
+fn parse_bungeecord_forwarding(server_address: &str) -> KeisteenResult<BungeeCordForwarding> {
+    let mut parts = server_address.split('\0').skip(1);
+    let (Some(address), Some(uuid)) = (parts.next(), parts.next()) else {
+        bail!("handshake does not contain forwarding data");
+    };
+
+    let properties = match parts.next() {
+        Some(properties) => {
+            serde_json::from_str(properties).wrap_err("invalid forwarded properties")?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(BungeeCordForwarding {
+        address: address.parse().wrap_err("invalid forwarded address")?,
+        uuid: Uuid::parse_str(uuid).wrap_err("invalid forwarded uuid")?,
+        properties,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mc::protocol::packet::server::ServerboundPacket;
+    use crate::mc::protocol::packet::server::login::{LoginAcknowledged, Start};
+    use crate::server::conn::ConnectionState;
+
+    const SECRET: &[u8] = b"s3cr3t";
+    // HMAC-SHA256 of `forwarded_player()` with `SECRET`.
+    const SIGNATURE: [u8; VELOCITY_SIGNATURE_LENGTH] = [
+        0xB7, 0x69, 0x93, 0x96, 0x80, 0xF2, 0x38, 0xA6, 0xB3, 0x77, 0x8C, 0x6D, 0x5D, 0xFE, 0x47,
+        0x89, 0xC8, 0x2E, 0xD4, 0x5F, 0x4F, 0xA8, 0x91, 0x3B, 0x70, 0xFF, 0xF8, 0xE0, 0x7A, 0x0F,
+        0xD8, 0xA7,
+    ];
+    const UUID: Uuid = Uuid::from_u128(0x069a79f4_44e9_4726_a5be_fca90e38aaf5);
+
+    fn forwarded_player() -> Vec<u8> {
+        let mut data = PacketData::new();
+        data.write(VarInt::new(VELOCITY_FORWARDING_VERSION as i32));
+        data.write("192.0.2.1");
+        let mut forwarded = data.bytes().to_vec();
+        // Velocity writes the UUID as two big-endian longs.
+        forwarded.extend_from_slice(UUID.as_bytes());
+
+        let mut data = PacketData::new();
+        data.write("Steve");
+        data.write_prefixed(vec![ProfileProperty::new("textures", "abc", Some("sig".into()))]);
+        forwarded.extend_from_slice(data.bytes());
+        forwarded
+    }
+
+    fn signed(signature: [u8; VELOCITY_SIGNATURE_LENGTH], forwarded: Vec<u8>) -> Vec<u8> {
+        [signature.to_vec(), forwarded].concat()
+    }
+
+    #[test]
+    fn accepts_signed_velocity_forwarding() {
+        let player = parse_velocity_forwarding(SECRET, signed(SIGNATURE, forwarded_player()))
+            .expect("forwarding should be accepted");
+
+        assert_eq!(player.address, IpAddr::from([192, 0, 2, 1]));
+        assert_eq!(player.profile.uuid(), UUID);
+        assert_eq!(player.profile.username(), "Steve");
+
+        let textures = player.profile.textures().expect("textures should be forwarded");
+        assert_eq!(textures.value, "abc");
+        assert_eq!(textures.signature.as_deref(), Some("sig"));
+    }
+
+    #[test]
+    fn rejects_tampered_velocity_forwarding() {
+        let mut signature = SIGNATURE;
+        signature[0] ^= 1;
+        assert!(parse_velocity_forwarding(SECRET, signed(signature, forwarded_player())).is_err());
+
+        let mut forwarded = forwarded_player();
+        *forwarded.last_mut().unwrap() ^= 1;
+        assert!(parse_velocity_forwarding(SECRET, signed(SIGNATURE, forwarded)).is_err());
+
+        let data = signed(SIGNATURE, forwarded_player());
+        assert!(parse_velocity_forwarding(b"other secret", data).is_err());
+        assert!(parse_velocity_forwarding(SECRET, SIGNATURE[..16].to_vec()).is_err());
+    }
+
+    fn velocity_login() -> Connection {
+        let config = "forwarding_mode = \"velocity\"\nforwarding_secret = \"s3cr3t\"";
+        let (mut conn, _client) = Connection::for_test(config);
+        conn.state = ConnectionState::Login;
+
+        let start = Start { name: "Steve".to_string(), player_uuid: Uuid::new_v4() };
+        start.handle(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn rejects_login_acknowledged_before_forwarding_is_answered() {
+        let mut conn = velocity_login();
+
+        assert!(LoginAcknowledged.handle(&mut conn).is_err());
+        assert!(matches!(conn.state, ConnectionState::Login));
+    }
+
+    #[test]
+    fn rejects_login_acknowledged_after_unverified_forwarding() {
+        let mut conn = velocity_login();
+        let mut signature = SIGNATURE;
+        signature[0] ^= 1;
+        conn.receive_login_plugin_response(0, Some(signed(signature, forwarded_player()))).unwrap();
+
+        assert!(LoginAcknowledged.handle(&mut conn).is_err());
+        assert_ne!(conn.player_profile.as_ref().map(PlayerProfile::uuid), Some(UUID));
+    }
+
+    #[test]
+    fn logs_in_with_the_forwarded_profile() {
+        let mut conn = velocity_login();
+        conn.receive_login_plugin_response(0, Some(signed(SIGNATURE, forwarded_player()))).unwrap();
+
+        LoginAcknowledged.handle(&mut conn).unwrap();
+        assert_eq!(conn.player_profile().uuid(), UUID);
+        assert_eq!(conn.remote_address().ip(), IpAddr::from([192, 0, 2, 1]));
+    }
+
+    #[test]
+    fn parses_bungeecord_forwarding() {
+        let forwarding = parse_bungeecord_forwarding(
+            "mc.example.com\0192.0.2.1\0069a79f444e94726a5befca90e38aaf5\0\
+             [{\"name\":\"textures\",\"value\":\"abc\"}]",
+        )
+        .unwrap();
+        assert_eq!(forwarding.address, IpAddr::from([192, 0, 2, 1]));
+        assert_eq!(forwarding.uuid, UUID);
+        assert_eq!(forwarding.properties.len(), 1);
+
+        assert!(parse_bungeecord_forwarding("mc.example.com").is_err());
+        assert!(parse_bungeecord_forwarding("mc.example.com\0192.0.2.1\0not-a-uuid").is_err());
+    }
+}
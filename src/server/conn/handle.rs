@@ -0,0 +1,114 @@
+use std::sync::{Arc, mpsc};
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::RawPacket;
+use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::Identifier;
+use crate::server::conn::cookie::CookieResult;
+use crate::server::conn::login_query::LoginQueryResult;
+use crate::server::conn::{Connection, ConnectionId};
+
+pub(crate) type ConnectionTask = Box<dyn FnOnce(&mut Connection) -> KeisteenResult<()> + Send>;
+
+/// A handle to the network thread, which can be used from any thread to act on connections.
+#[derive(Clone)]
+pub struct NetworkHandle {
+    tasks: mpsc::Sender<(ConnectionId, ConnectionTask)>,
+    waker: Arc<mio::Waker>,
+}
+
+impl NetworkHandle {
+    pub(crate) fn new(
+        tasks: mpsc::Sender<(ConnectionId, ConnectionTask)>,
+        waker: Arc<mio::Waker>,
+    ) -> Self {
+        Self { tasks, waker }
+    }
+
+    /// Schedules `task` to run on the network thread for the given connection.
+    ///
+    /// The task is silently dropped if the connection has been closed in the meantime.
+    pub(crate) fn execute<F>(&self, connection: ConnectionId, task: F)
+    where
+        F: FnOnce(&mut Connection) -> KeisteenResult<()> + Send + 'static,
+    {
+        if self.tasks.send((connection, Box::new(task))).is_err() {
+            log::warn!("network thread has stopped, dropping task for {connection:?}");
+            return;
+        }
+
+        if let Err(err) = self.waker.wake() {
+            log::error!("failed to wake network thread: {err}");
+        }
+    }
+
+    pub fn connection(&self, id: ConnectionId) -> ConnectionHandle {
+        ConnectionHandle { id, network: self.clone() }
+    }
+}
+
+/// A handle to a single connection, which can be used from any thread.
+#[derive(Clone)]
+pub struct ConnectionHandle {
+    id: ConnectionId,
+    network: NetworkHandle,
+}
+
+impl ConnectionHandle {
+    pub fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    pub fn send_packet<P: ClientboundPacket>(&self, packet: P) {
+        let packet = RawPacket::from_clientbound(packet);
+        self.network.execute(self.id, move |conn| Ok(conn.send_raw_packet(packet)?));
+    }
+
+    pub fn disconnect(&self, reason: Option<TextComponent>) {
+        self.network.execute(self.id, move |conn| {
+            conn.disconnect(reason);
+            Ok(())
+        });
+    }
+
+    /// Asks the client for a cookie, see [Connection::request_cookie].
+    pub fn request_cookie<F>(&self, key: Identifier, callback: F)
+    where
+        F: FnOnce(&mut Connection, CookieResult) -> KeisteenResult<()> + Send + 'static,
+    {
+        self.network.execute(self.id, move |conn| conn.request_cookie(key, callback));
+    }
+
+    /// Stores a cookie on the client, see [Connection::store_cookie].
+    pub fn store_cookie(&self, key: Identifier, payload: Vec<u8>) {
+        self.network.execute(self.id, move |conn| conn.store_cookie(key, payload));
+    }
+
+    /// Sends a login plugin request, see [Connection::send_login_plugin_request].
+    pub fn send_login_plugin_request<F>(&self, channel: Identifier, data: Vec<u8>, callback: F)
+    where
+        F: FnOnce(&mut Connection, LoginQueryResult) -> KeisteenResult<()> + Send + 'static,
+    {
+        self.network
+            .execute(self.id, move |conn| conn.send_login_plugin_request(channel, data, callback));
+    }
+
+    /// Sends a plugin message to the client, see [Connection::send_plugin_message].
+    pub fn send_plugin_message(&self, channel: Identifier, data: Vec<u8>) {
+        self.network.execute(self.id, move |conn| conn.send_plugin_message(channel, data));
+    }
+
+    /// Sends the client to another server, see [Connection::transfer].
+    pub fn transfer(&self, host: impl Into<String>, port: u16) {
+        let host = host.into();
+        self.network.execute(self.id, move |conn| conn.transfer(host, port));
+    }
+
+    pub(crate) fn execute<F>(&self, task: F)
+    where
+        F: FnOnce(&mut Connection) -> KeisteenResult<()> + Send + 'static,
+    {
+        self.network.execute(self.id, task);
+    }
+}
@@ -0,0 +1,92 @@
+// Server list ping used by clients older than 1.7.
+// https://minecraft.wiki/w/Java_Edition_protocol/Server_List_Ping#1.6
+
+const LEGACY_PING_ID: u8 = 0xFE;
+const LEGACY_KICK_ID: u8 = 0xFF;
+const PLUGIN_MESSAGE_ID: u8 = 0xFA;
+// Legacy clients can never join, so they should always see the server as incompatible.
+const LEGACY_PROTOCOL_VERSION: i32 = 127;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyPing {
+    /// Beta 1.8 to 1.3 only send `0xFE`.
+    Beta,
+    /// 1.4 to 1.6 send `0xFE 0x01`, optionally followed by a `MC|PingHost` plugin message.
+    V1_4,
+}
+
+/// What the first bytes received on a fresh connection are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detection {
+    Ping(LegacyPing),
+    /// The bytes are a legacy ping if nothing follows them, but may also be the start of a
+    /// handshake whose remaining bytes have not been received yet.
+    Ambiguous(LegacyPing),
+    NotLegacy,
+}
+
+impl LegacyPing {
+    /// Detects a legacy ping from the first bytes that were received on a fresh connection.
+    ///
+    /// Modern clients always start with a handshake packet, whose length is a VarInt. That length
+    /// can start with `0xFE` when it takes multiple bytes, so a lone `0xFE` is only a Beta ping if
+    /// nothing follows it. A handshake of 254 bytes even starts with `0xFE 0x01`, but continues
+    /// with its packet id `0x00`, where 1.6 clients continue with a `0xFA` plugin message.
+    pub fn detect(first_bytes: &[u8]) -> Detection {
+        match first_bytes {
+            [LEGACY_PING_ID] => Detection::Ambiguous(Self::Beta),
+            [LEGACY_PING_ID, 0x01] => Detection::Ambiguous(Self::V1_4),
+            [LEGACY_PING_ID, 0x01, PLUGIN_MESSAGE_ID, ..] => Detection::Ping(Self::V1_4),
+            _ => Detection::NotLegacy,
+        }
+    }
+
+    pub fn encode_response(&self, motd: &str, online_players: i32, max_players: i32) -> Vec<u8> {
+        let response = match self {
+            Self::Beta => {
+                // Section signs are used as separators, so they can't appear in the MOTD.
+                let motd = motd.replace('§', "");
+                format!("{motd}§{online_players}§{max_players}")
+            }
+            Self::V1_4 => format!(
+                "§1\0{LEGACY_PROTOCOL_VERSION}\0{}\0{motd}\0{online_players}\0{max_players}",
+                crate::MC_VERSION
+            ),
+        };
+
+        let chars = response.encode_utf16().collect::<Vec<_>>();
+
+        let mut bytes = Vec::with_capacity(3 + chars.len() * 2);
+        bytes.push(LEGACY_KICK_ID);
+        bytes.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+        for char in chars {
+            bytes.extend_from_slice(&char.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_legacy_pings() {
+        let detection = LegacyPing::detect(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]);
+        assert_eq!(detection, Detection::Ping(LegacyPing::V1_4));
+    }
+
+    #[test]
+    fn waits_for_more_bytes_after_ambiguous_prefixes() {
+        assert_eq!(LegacyPing::detect(&[0xFE]), Detection::Ambiguous(LegacyPing::Beta));
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01]), Detection::Ambiguous(LegacyPing::V1_4));
+    }
+
+    #[test]
+    fn does_not_mistake_long_handshakes_for_legacy_pings() {
+        // A handshake of 254 bytes has a length of `0xFE 0x01`, followed by its packet id.
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x01, 0x00, 0xFC, 0x05]), Detection::NotLegacy);
+        assert_eq!(LegacyPing::detect(&[0xFE, 0x02, 0x00]), Detection::NotLegacy);
+        assert_eq!(LegacyPing::detect(&[0x10, 0x00]), Detection::NotLegacy);
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use eyre::bail;
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::{PacketData, client};
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::{Identifier, VarInt};
+use crate::server::conn::{Connection, ConnectionState};
+
+/// The maximum size of a login plugin response sent by a client, in bytes.
+pub const MAX_LOGIN_PLUGIN_RESPONSE_SIZE: usize = 1024 * 1024;
+
+/// The data of a login plugin response, or `None` if the client did not understand the request.
+pub type LoginQueryResult = Option<Vec<u8>>;
+
+type LoginQueryCallback =
+    Box<dyn FnOnce(&mut Connection, LoginQueryResult) -> KeisteenResult<()> + Send>;
+type ConnectionCallback = Box<dyn FnOnce(&mut Connection) -> KeisteenResult<()> + Send>;
+
+/// Login plugin requests that are waiting for a response from the client.
+#[derive(Default)]
+pub(crate) struct LoginQueries {
+    next_message_id: i32,
+    pending: HashMap<i32, LoginQueryCallback>,
+    on_answered: Option<ConnectionCallback>,
+}
+
+impl Connection {
+    /// Sends a login plugin request to the client. The callback runs on the network thread once
+    /// the client answers. Logging in does not complete until all requests have been answered.
+    pub fn send_login_plugin_request<F>(
+        &mut self,
+        channel: Identifier,
+        data: Vec<u8>,
+        callback: F,
+    ) -> KeisteenResult<()>
+    where
+        F: FnOnce(&mut Connection, LoginQueryResult) -> KeisteenResult<()> + Send + 'static,
+    {
+        if !matches!(self.state, ConnectionState::Login) {
+            bail!("cannot send login plugin requests in {:?} state", self.state);
+        }
+
+        let message_id = self.login_queries.next_message_id;
+        self.login_queries.next_message_id += 1;
+
+        self.send_packet(client::login::LoginPluginRequest {
+            message_id: VarInt::new(message_id),
+            channel,
+            data,
+        })?;
+        self.login_queries.pending.insert(message_id, Box::new(callback));
+
+        Ok(())
+    }
+
+    /// Runs `f` once all login plugin requests have been answered, or right away if there are
+    /// none.
+    pub(crate) fn after_login_queries<F>(&mut self, f: F) -> KeisteenResult<()>
+    where
+        F: FnOnce(&mut Connection) -> KeisteenResult<()> + Send + 'static,
+    {
+        if self.login_queries.pending.is_empty() {
+            return f(self);
+        }
+
+        self.login_queries.on_answered = Some(Box::new(f));
+
+        Ok(())
+    }
+
+    pub(crate) fn receive_login_plugin_response(
+        &mut self,
+        message_id: i32,
+        data: LoginQueryResult,
+    ) -> KeisteenResult<()> {
+        let Some(callback) = self.login_queries.pending.remove(&message_id) else {
+            log::warn!("{} answered unknown login query {message_id}", self.remote_address);
+            self.disconnect(Some(TextComponent::translatable(
+                "multiplayer.disconnect.unexpected_query_response",
+                vec![],
+            )));
+            return Ok(());
+        };
+
+        callback(self, data)?;
+
+        if self.is_running
+            && self.login_queries.pending.is_empty()
+            && let Some(on_answered) = self.login_queries.on_answered.take()
+        {
+            on_answered(self)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the optional data of a login plugin response, which spans the rest of the packet.
+pub(crate) fn read_login_plugin_response_data(
+    data: &mut PacketData,
+) -> KeisteenResult<LoginQueryResult> {
+    if !data.read::<bool>()? {
+        return Ok(None);
+    }
+
+    let length = data.bytes().len();
+    if length > MAX_LOGIN_PLUGIN_RESPONSE_SIZE {
+        bail!("login plugin response of {length} bytes exceeds {MAX_LOGIN_PLUGIN_RESPONSE_SIZE}");
+    }
+
+    Ok(Some(data.read_predefined(length)?))
+}
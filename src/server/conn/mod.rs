@@ -1,128 +1,597 @@
-use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
-use std::{io, thread};
-
-use crate::error::KeisteenResult;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, ToSocketAddrs};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
+
+use eyre::{ContextCompat, bail, eyre};
+use mio::net::{TcpListener, TcpStream};
+use mio::{Events, Interest, Poll, Registry, Token, Waker};
+
+use crate::error::{KeisteenError, KeisteenResult};
 use crate::mc::protocol::packet::RawPacket;
-use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::protocol::packet::client::{self, ClientboundPacket};
+use crate::mc::text::ansi::AnsiRenderer;
 use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::{Identifier, VarInt};
 use crate::server::ServerHandle;
+use crate::server::client_settings::ClientSettings;
+use crate::server::conn::cookie::PendingCookies;
+use crate::server::conn::forwarding::BungeeCordForwarding;
+use crate::server::conn::handle::{ConnectionHandle, ConnectionTask, NetworkHandle};
+use crate::server::conn::legacy_ping::{Detection, LegacyPing};
+use crate::server::conn::login_query::LoginQueries;
 use crate::server::conn::packet::decoder::PacketDecoder;
 use crate::server::conn::packet::encoder::PacketEncoder;
+use crate::server::conn::proxy_protocol::ProxyHeader;
+use crate::server::keep_alive::{KeepAlive, KeepAliveTick};
 use crate::server::player_profile::PlayerProfile;
+use crate::server::tick::QueuedPacket;
+
+pub mod cookie;
+pub mod forwarding;
+pub mod handle;
+pub mod login_query;
+pub mod plugin_message;
 
+mod legacy_ping;
 mod packet;
+mod proxy_protocol;
+
+const LISTENER: Token = Token(0);
+const WAKER: Token = Token(1);
+
+const READ_BUFFER_SIZE: usize = 4096;
+
+const CONNECTION_TICK_INTERVAL: Duration = Duration::from_secs(1);
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a connection that is not yet playing may stay silent before it is dropped, like the
+/// read timeout of vanilla.
+const READ_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long to wait for more bytes after bytes that are either a legacy ping or the start of a
+/// handshake, before answering them as a legacy ping.
+const LEGACY_PING_TIMEOUT: Duration = Duration::from_millis(500);
+/// How long a closed connection is kept open to send its pending packets, like a disconnect reason.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Reading from a connection is paused while its pending outgoing bytes are above the high
+// watermark, and is resumed once they have been drained below the low watermark. Connections
+// that fall behind further than the limit are dropped.
+const WRITE_HIGH_WATERMARK: usize = 1024 * 1024;
+const WRITE_LOW_WATERMARK: usize = 256 * 1024;
+const WRITE_BUFFER_LIMIT: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConnectionId(usize);
 
 pub struct ConnectionManager {
     server: ServerHandle,
+
+    poll: Poll,
+    network: NetworkHandle,
+    tasks: mpsc::Receiver<(ConnectionId, ConnectionTask)>,
+    packets: mpsc::Sender<QueuedPacket>,
+
+    listener: Option<TcpListener>,
+    connections: HashMap<Token, Connection>,
+    next_token: usize,
 }
 
 impl ConnectionManager {
-    pub fn new(server: ServerHandle) -> Self {
-        Self { server }
+    pub fn new(server: ServerHandle, packets: mpsc::Sender<QueuedPacket>) -> KeisteenResult<Self> {
+        let poll = Poll::new()?;
+        let waker = Arc::new(Waker::new(poll.registry(), WAKER)?);
+        let (sender, tasks) = mpsc::channel();
+
+        Ok(Self {
+            server,
+
+            poll,
+            network: NetworkHandle::new(sender, waker),
+            tasks,
+            packets,
+
+            listener: None,
+            connections: HashMap::new(),
+            next_token: WAKER.0 + 1,
+        })
+    }
+
+    pub fn network(&self) -> &NetworkHandle {
+        &self.network
+    }
+
+    pub fn bind<A: ToSocketAddrs>(&mut self, addr: A) -> KeisteenResult<()> {
+        let addr = addr.to_socket_addrs()?.next().wrap_err("no address to bind to")?;
+        let mut listener = TcpListener::bind(addr)?;
+        self.poll.registry().register(&mut listener, LISTENER, Interest::READABLE)?;
+        log::info!("started listening on {}", listener.local_addr()?);
+
+        self.listener = Some(listener);
+
+        Ok(())
     }
 
-    pub fn bind<A: ToSocketAddrs>(self, addr: A) -> KeisteenResult<()> {
-        let listener = TcpListener::bind(addr)?;
-        log::info!("started listening on {}", listener.local_addr().unwrap());
+    /// Runs the network event loop on the current thread.
+    pub fn run(mut self) -> KeisteenResult<()> {
+        let listener = self.listener.take().wrap_err("network is not bound to an address")?;
+
+        let mut events = Events::with_capacity(1024);
+        let mut next_connection_tick = Instant::now() + CONNECTION_TICK_INTERVAL;
+        loop {
+            let timeout = next_connection_tick.saturating_duration_since(Instant::now());
+            if let Err(err) = self.poll.poll(&mut events, Some(timeout)) {
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(err.into());
+            }
+
+            for event in &events {
+                match event.token() {
+                    LISTENER => self.accept_connections(&listener),
+                    WAKER => self.run_tasks(),
+                    token => self.handle_event(token, event.is_readable()),
+                }
+            }
+
+            if Instant::now() >= next_connection_tick {
+                self.tick_connections();
+                next_connection_tick = Instant::now() + CONNECTION_TICK_INTERVAL;
+            }
+        }
+    }
 
-        for stream in listener.incoming() {
-            match stream {
-                Ok(stream) => {
-                    stream.set_nodelay(true)?;
-                    Connection::new(stream, self.server.clone())?.spawn();
+    fn accept_connections(&mut self, listener: &TcpListener) {
+        loop {
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    if let Err(err) = self.register_connection(stream, address) {
+                        log::error!("failed to register connection from {address}: {err}");
+                    }
                 }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
                 Err(err) => {
-                    log::error!("failed to accept incoming connection: {err}")
+                    log::error!("failed to accept incoming connection: {err}");
+                    break;
                 }
             }
         }
+    }
+
+    fn register_connection(
+        &mut self,
+        mut stream: TcpStream,
+        address: SocketAddr,
+    ) -> KeisteenResult<()> {
+        stream.set_nodelay(true)?;
+
+        let token = Token(self.next_token);
+        self.next_token += 1;
+        self.poll.registry().register(&mut stream, token, Interest::READABLE)?;
+
+        let id = ConnectionId(token.0);
+        let conn = Connection::new(
+            id,
+            stream,
+            address,
+            self.server.clone(),
+            self.network.clone(),
+            self.packets.clone(),
+        );
+        // Behind a load balancer, the client address is only known once the PROXY header is read.
+        if !conn.awaits_proxy_header {
+            log::info!("new connection: {address}");
+        }
+        self.connections.insert(token, conn);
 
         Ok(())
     }
+
+    fn handle_event(&mut self, token: Token, is_readable: bool) {
+        let Some(conn) = self.connections.get_mut(&token) else { return };
+
+        if is_readable && let Err(err) = catch_panic(|| conn.receive()) {
+            conn.fail(err);
+        }
+
+        if let Err(err) = conn.flush() {
+            conn.fail(err.into());
+        }
+
+        if let Err(err) = catch_panic(|| conn.process_packets()) {
+            conn.fail(err);
+        }
+
+        self.update_connection(token);
+    }
+
+    fn run_tasks(&mut self) {
+        while let Ok((id, task)) = self.tasks.try_recv() {
+            let token = Token(id.0);
+            let Some(conn) = self.connections.get_mut(&token) else { continue };
+            if !conn.is_running {
+                continue;
+            }
+
+            if let Err(err) = catch_panic(|| task(conn)) {
+                conn.fail(err);
+            }
+
+            self.update_connection(token);
+        }
+    }
+
+    fn tick_connections(&mut self) {
+        let tokens: Vec<Token> = self.connections.keys().copied().collect();
+        for token in tokens {
+            let conn = self.connections.get_mut(&token).expect("connection should exist");
+            if conn.is_running
+                && let Err(err) = catch_panic(|| conn.tick())
+            {
+                conn.fail(err);
+            }
+
+            self.update_connection(token);
+        }
+    }
+
+    fn update_connection(&mut self, token: Token) {
+        let Some(conn) = self.connections.get_mut(&token) else { return };
+
+        let mut is_writable = true;
+        if let Err(err) = conn.flush() {
+            conn.fail(err.into());
+            is_writable = false;
+        }
+
+        if conn.is_running || (is_writable && conn.is_closing()) {
+            if let Err(err) = conn.update_interest(self.poll.registry()) {
+                conn.fail(err.into());
+            } else {
+                return;
+            }
+        }
+
+        let mut conn = self.connections.remove(&token).expect("connection should exist");
+        let _ = self.poll.registry().deregister(&mut conn.stream);
+        let _ = conn.stream.shutdown(Shutdown::Both);
+        log::debug!("connection closed: {}", conn.remote_address);
+    }
+}
+
+/// Runs code that handles a connection, turning a panic into an error so that a bug in a packet
+/// handler only takes down the connection that triggered it instead of the thread it runs on.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> KeisteenResult<T>) -> KeisteenResult<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Err(eyre!("connection handler panicked: {message}"))
+        }
+    }
 }
 
 pub struct Connection {
+    id: ConnectionId,
     is_running: bool,
+    closed_at: Option<Instant>,
 
     server: ServerHandle,
+    network: NetworkHandle,
+    packets: mpsc::Sender<QueuedPacket>,
 
     pub(crate) state: ConnectionState,
+    pub(crate) is_transferred: bool,
 
-    writer: PacketEncoder<TcpStream>,
-    reader: PacketDecoder<TcpStream>,
+    writer: PacketEncoder,
+    reader: PacketDecoder,
     stream: TcpStream,
-
+    peer_address: SocketAddr,
+    remote_address: SocketAddr,
+    awaits_proxy_header: bool,
+    connected_at: Instant,
+    last_read_at: Instant,
+    interest: Interest,
+    is_backlogged: bool,
+
+    pub(crate) keep_alive: KeepAlive,
+    pending_cookies: PendingCookies,
+    login_queries: LoginQueries,
+    client_brand: Option<String>,
+    pub(crate) client_settings: ClientSettings,
+    client_channels: HashSet<Identifier>,
+    bungeecord_forwarding: Option<BungeeCordForwarding>,
+    pub(crate) login_state: LoginState,
     pub(crate) player_profile: Option<PlayerProfile>,
 }
 
 impl Connection {
-    pub fn new(stream: TcpStream, server: ServerHandle) -> KeisteenResult<Self> {
-        Ok(Self {
-            is_running: false,
+    fn new(
+        id: ConnectionId,
+        stream: TcpStream,
+        peer_address: SocketAddr,
+        server: ServerHandle,
+        network: NetworkHandle,
+        packets: mpsc::Sender<QueuedPacket>,
+    ) -> Self {
+        let awaits_proxy_header =
+            server.read(|server| server.server_folder().config().proxy_protocol());
+
+        Self {
+            id,
+            is_running: true,
+            closed_at: None,
 
             server,
+            network,
+            packets,
 
             state: ConnectionState::Handshaking,
+            is_transferred: false,
 
-            writer: PacketEncoder::new(stream.try_clone()?),
-            reader: PacketDecoder::new(stream.try_clone()?),
+            writer: PacketEncoder::new(),
+            reader: PacketDecoder::new(),
             stream,
-
+            peer_address,
+            remote_address: peer_address,
+            awaits_proxy_header,
+            connected_at: Instant::now(),
+            last_read_at: Instant::now(),
+            interest: Interest::READABLE,
+            is_backlogged: false,
+
+            keep_alive: KeepAlive::new(),
+            pending_cookies: PendingCookies::default(),
+            login_queries: LoginQueries::default(),
+            client_brand: None,
+            client_settings: ClientSettings::default(),
+            client_channels: HashSet::new(),
+            bungeecord_forwarding: None,
+            login_state: LoginState::AwaitingStart,
             player_profile: None,
-        })
+        }
+    }
+
+    pub fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    pub fn handle(&self) -> ConnectionHandle {
+        self.network.connection(self.id)
     }
 
     pub fn server(&self) -> &ServerHandle {
         &self.server
     }
 
+    pub fn peer_address(&self) -> SocketAddr {
+        self.peer_address
+    }
+
+    /// The address of the client. This differs from the peer address when the client connects
+    /// through a proxy or load balancer that forwards its address.
+    pub fn remote_address(&self) -> SocketAddr {
+        self.remote_address
+    }
+
+    /// Whether the client connected to this server because another server transferred it here.
+    pub fn is_transferred(&self) -> bool {
+        self.is_transferred
+    }
+
+    /// The settings the client has sent, which are known from the configuration state on.
+    pub fn client_settings(&self) -> &ClientSettings {
+        &self.client_settings
+    }
+
+    /// The locale the client is set to, e.g. `en_us`, which is known from the configuration
+    /// state on.
+    pub fn locale(&self) -> &str {
+        &self.client_settings.locale
+    }
+
+    /// Translates the keys of a component that the server translates into the client's locale.
+    /// Every text component the connection sends goes through this.
+    pub fn localize(&self, component: &TextComponent) -> TextComponent {
+        self.server.read(|server| server.translations().localize(component, self.locale()))
+    }
+
     pub fn player_profile(&self) -> &PlayerProfile {
         self.player_profile.as_ref().expect("player should have been initialized at login")
     }
 
-    pub(crate) fn spawn(mut self) {
-        let peer_address = self
-            .stream
-            .peer_addr()
-            .map(|a| a.to_string())
-            .unwrap_or("<no peer address>".to_string());
-
-        thread::Builder::new()
-            .name(format!("connection [{}]", peer_address))
-            .spawn(move || {
-                log::info!("new connection: {}", peer_address);
-                if let Err(error) = self.run() {
-                    log::error!(
-                        "thread '{}' in {:?} state stopped: {}",
-                        thread::current().name().unwrap_or("<unnamed>"),
-                        &self.state,
-                        error
-                    );
+    fn receive(&mut self) -> KeisteenResult<()> {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        while self.is_running && !self.is_backlogged {
+            match self.stream.read(&mut buf) {
+                Ok(0) => {
+                    self.disconnect(None);
+                    break;
                 }
+                Ok(read) => {
+                    self.last_read_at = Instant::now();
+                    self.reader.receive(&mut buf[..read]);
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
 
-                self.disconnect(None);
-            })
-            .expect("should create thread");
+        Ok(())
     }
 
-    fn run(&mut self) -> KeisteenResult<()> {
-        self.is_running = true;
+    fn process_packets(&mut self) -> KeisteenResult<()> {
+        while self.is_running && !self.is_backlogged {
+            if self.awaits_proxy_header {
+                let Some(header) = ProxyHeader::parse(self.reader.buffered())? else { break };
+                self.reader.consume(header.length);
+                self.awaits_proxy_header = false;
+
+                match header.source {
+                    Some(source) => {
+                        log::info!("new connection: {source} (proxied by {})", self.peer_address);
+                        self.remote_address = source;
+                    }
+                    None => log::info!("new connection: {}", self.peer_address),
+                }
+                continue;
+            }
 
-        while self.is_running {
-            log::trace!("waiting for next packet in {:?} state...", self.state);
-            let packet = self.read_packet()?;
+            if let ConnectionState::Handshaking = self.state {
+                match LegacyPing::detect(self.reader.buffered()) {
+                    Detection::Ping(legacy_ping) => return self.answer_legacy_ping(legacy_ping),
+                    // Answered by the next tick if no more bytes arrive.
+                    Detection::Ambiguous(_) => break,
+                    Detection::NotLegacy => {}
+                }
+            }
+
+            let Some(packet) = self.reader.next_packet()? else { break };
+            log::trace!("received packet {} in {:?} state", packet.packet_id, self.state);
             self.handle_raw_packet(packet)?;
         }
 
         Ok(())
     }
 
+    /// Runs periodic work for connections that are not yet handled by the server thread.
+    fn tick(&mut self) -> KeisteenResult<()> {
+        self.expire_cookie_requests()?;
+
+        if let ConnectionState::Handshaking = self.state
+            && let Detection::Ambiguous(legacy_ping) = LegacyPing::detect(self.reader.buffered())
+            && self.last_read_at.elapsed() > LEGACY_PING_TIMEOUT
+        {
+            return self.answer_legacy_ping(legacy_ping);
+        }
+
+        if !matches!(self.state, ConnectionState::Play)
+            && self.last_read_at.elapsed() > READ_IDLE_TIMEOUT
+        {
+            self.disconnect(Some(KeepAlive::timeout_reason()));
+            return Ok(());
+        }
+
+        match self.state {
+            // Logging in can be held up by authentication and login plugin requests.
+            ConnectionState::Login if self.connected_at.elapsed() > LOGIN_TIMEOUT => {
+                self.disconnect(Some(TextComponent::translatable(
+                    "multiplayer.disconnect.slow_login",
+                    vec![],
+                )));
+            }
+            ConnectionState::Config => match self.keep_alive.tick() {
+                KeepAliveTick::Idle => {}
+                KeepAliveTick::Send(id) => self.send_packet(client::config::KeepAlive { id })?,
+                KeepAliveTick::TimedOut => self.disconnect(Some(KeepAlive::timeout_reason())),
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn queue_play_packet(&mut self, packet: RawPacket) -> KeisteenResult<()> {
+        self.packets
+            .send(QueuedPacket { connection: self.id, packet })
+            .ok()
+            .wrap_err("server thread has stopped")
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        while !self.writer.pending().is_empty() {
+            match self.stream.write(self.writer.pending()) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => self.writer.advance(written),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+
+        let pending = self.writer.pending().len();
+        if pending > WRITE_HIGH_WATERMARK {
+            self.is_backlogged = true;
+        } else if pending < WRITE_LOW_WATERMARK {
+            self.is_backlogged = false;
+        }
+
+        Ok(())
+    }
+
+    fn update_interest(&mut self, registry: &Registry) -> io::Result<()> {
+        let is_reading = self.is_running && !self.is_backlogged;
+        let interest = match (is_reading, self.writer.pending().is_empty()) {
+            (true, true) => Interest::READABLE,
+            (true, false) => Interest::READABLE | Interest::WRITABLE,
+            (false, _) => Interest::WRITABLE,
+        };
+
+        if interest != self.interest {
+            registry.reregister(&mut self.stream, Token(self.id.0), interest)?;
+            self.interest = interest;
+        }
+
+        Ok(())
+    }
+
+    fn fail(&mut self, error: KeisteenError) {
+        log::error!(
+            "connection {} in {:?} state stopped: {}",
+            self.remote_address,
+            self.state,
+            error
+        );
+        self.disconnect(None);
+    }
+
+    fn answer_legacy_ping(&mut self, legacy_ping: LegacyPing) -> KeisteenResult<()> {
+        log::debug!("received legacy server list ping ({legacy_ping:?})");
+
+        if !self.server.read(|server| server.server_folder().config().properties().enable_status) {
+            self.close();
+            return Ok(());
+        }
+
+        let status = self.server.read(|server| server.status());
+        let (online_players, max_players) =
+            status.players.map_or((0, 0), |players| (players.online, players.max));
+        let response = legacy_ping.encode_response(
+            &status.description.to_string(),
+            online_players,
+            max_players,
+        );
+
+        self.writer.write_unframed(&response);
+        self.close();
+
+        Ok(())
+    }
+
+    /// Closes the connection. If a reason is given, it is sent to the client first in the
+    /// disconnect packet of the current state, so the client can show it.
     pub fn disconnect(&mut self, reason: Option<TextComponent>) {
+        if let Some(reason) = &reason
+            && let Err(err) = self.send_disconnect_packet(reason.clone())
+        {
+            log::error!("failed to send disconnect reason to {}: {err}", self.remote_address);
+        }
+
         if let Some(profile) = &self.player_profile {
             log::info!(
                 "player '{}' disconnected{}",
                 profile.username(),
-                if let Some(reason) = reason { format!(": {}", reason) } else { String::new() }
+                match reason {
+                    Some(reason) => format!(": {}", AnsiRenderer::console().render(&reason)),
+                    None => String::new(),
+                }
             );
             self.server.update(|server| server.player_list_mut().remove_player(profile.uuid()));
         }
@@ -132,18 +601,49 @@ impl Connection {
         self.close();
     }
 
+    fn send_disconnect_packet(&mut self, reason: TextComponent) -> io::Result<()> {
+        let reason = self.localize(&reason);
+
+        match self.state {
+            // The client is not expecting any text before it has started logging in.
+            ConnectionState::Handshaking | ConnectionState::Status => Ok(()),
+            ConnectionState::Login => self.send_packet(client::login::Disconnected { reason }),
+            ConnectionState::Config => self.send_packet(client::config::Disconnected { reason }),
+            ConnectionState::Play => self.send_packet(client::play::Disconnect { reason }),
+        }
+    }
+
     fn close(&mut self) {
-        let _ = self.stream.shutdown(Shutdown::Both);
+        // The connection is shut down by the network thread, after pending packets have been sent.
         self.is_running = false;
+        self.closed_at.get_or_insert_with(Instant::now);
     }
 
-    pub(crate) fn enable_encryption(&mut self, shared_secret: &[u8]) -> KeisteenResult<()> {
-        let shared_secret = self.server.read(|server| {
-            server.crypt_keys().decrypt(shared_secret).expect("should decrypt secret")
-        });
+    /// Whether the connection has been closed but is still sending its pending packets.
+    fn is_closing(&self) -> bool {
+        !self.is_running
+            && !self.writer.pending().is_empty()
+            && self.closed_at.is_some_and(|closed_at| closed_at.elapsed() < CLOSE_TIMEOUT)
+    }
+
+    /// Sends the client to another server. Only possible in the configuration and play states.
+    pub fn transfer(&mut self, host: impl Into<String>, port: u16) -> KeisteenResult<()> {
+        let host = host.into();
+        log::info!("transferring {} to {host}:{port}", self.remote_address);
 
-        self.writer.enable_encryption(&shared_secret)?;
-        self.reader.enable_encryption(&shared_secret)?;
+        let port = VarInt::new(port as i32);
+        match self.state {
+            ConnectionState::Config => self.send_packet(client::config::Transfer { host, port })?,
+            ConnectionState::Play => self.send_packet(client::play::Transfer { host, port })?,
+            state => bail!("cannot transfer a connection in {state:?} state"),
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn enable_encryption(&mut self, shared_secret: &[u8]) -> KeisteenResult<()> {
+        self.writer.enable_encryption(shared_secret)?;
+        self.reader.enable_encryption(shared_secret)?;
 
         log::debug!("encryption enabled");
 
@@ -151,30 +651,43 @@ impl Connection {
     }
 
     pub(crate) fn enable_compression(&mut self) -> KeisteenResult<()> {
-        // TODO: Add the threshold to the config.
-        let threshold = 256;
-        // TODO: Add the level to the config.
-        let level = 3;
+        let (threshold, level) = self.server.read(|server| {
+            let config = server.server_folder().config();
+            (config.properties().network_compression_threshold, config.network_compression_level())
+        });
+
+        if threshold < 0 {
+            log::debug!("compression disabled");
+            return Ok(());
+        }
 
-        self.writer.enable_compression(threshold, level)?;
-        self.reader.enable_compression()?;
+        self.send_packet(client::login::SetCompression { threshold: VarInt::new(threshold) })?;
 
-        // TODO: Actually enable compression.
-        // self.send_packet(CLoginPacket::SetCompression {
-        //     threshold: VarInt::new(threshold as i32),
-        // })?;
+        self.writer.enable_compression(threshold as u32, level)?;
+        self.reader.enable_compression(threshold as u32)?;
 
-        log::debug!("compression enabled");
+        log::debug!("compression enabled with threshold {threshold}");
 
         Ok(())
     }
 
     pub(crate) fn send_packet<P: ClientboundPacket>(&mut self, packet: P) -> io::Result<()> {
-        self.writer.write_packet(packet)
+        self.send_raw_packet(RawPacket::from_clientbound(packet))
     }
 
-    pub(crate) fn read_packet(&mut self) -> io::Result<RawPacket> {
-        self.reader.read_packet()
+    pub(crate) fn send_raw_packet(&mut self, packet: RawPacket) -> io::Result<()> {
+        if !self.is_running {
+            return Ok(());
+        }
+
+        self.writer.write_packet(packet)?;
+
+        if self.writer.pending().len() > WRITE_BUFFER_LIMIT {
+            log::warn!("{} is not reading packets fast enough", self.remote_address);
+            self.disconnect(None);
+        }
+
+        Ok(())
     }
 }
 
@@ -183,7 +696,97 @@ pub enum ConnectionState {
     Handshaking,
     Status,
     Login,
-    Transfer,
     Config,
     Play,
 }
+
+/// How far logging in has progressed, so that packets the client sends out of order are rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LoginState {
+    AwaitingStart,
+    /// An encryption request has been sent that the client has not responded to yet.
+    AwaitingEncryption,
+    /// The session service is being asked whether the player has joined.
+    AwaitingAuthentication,
+    /// Waiting for the client to answer the login plugin requests.
+    AwaitingQueries,
+    /// Login success has been sent, which the client has to acknowledge.
+    SuccessSent,
+}
+
+#[cfg(test)]
+impl ConnectionId {
+    pub(crate) fn for_test(id: usize) -> Self {
+        Self(id)
+    }
+}
+
+#[cfg(test)]
+impl Connection {
+    /// Creates a connection to a server with the given `config.toml`, along with the client's end
+    /// of the socket.
+    pub(crate) fn for_test(config: &str) -> (Self, std::net::TcpStream) {
+        let server = crate::server::Server::for_test(config);
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, address) = listener.accept().unwrap();
+        stream.set_nonblocking(true).unwrap();
+
+        let poll = Poll::new().unwrap();
+        let waker = Arc::new(Waker::new(poll.registry(), WAKER).unwrap());
+        let (tasks, _) = mpsc::channel();
+        let (packets, _) = mpsc::channel();
+
+        let conn = Self::new(
+            ConnectionId(WAKER.0 + 1),
+            TcpStream::from_std(stream),
+            address,
+            ServerHandle::new(server),
+            NetworkHandle::new(tasks, waker),
+            packets,
+        );
+        (conn, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sends_pending_packets_after_closing() {
+        let (mut conn, mut client) = Connection::for_test("");
+
+        // More than the socket buffers can take while the client is not reading.
+        let data = vec![0; 32 * 1024 * 1024];
+        conn.writer.write_unframed(&data);
+        conn.disconnect(None);
+        conn.flush().unwrap();
+        assert!(conn.is_closing());
+
+        let mut received = 0;
+        let mut buf = vec![0; 64 * 1024];
+        while conn.is_closing() {
+            received += client.read(&mut buf).unwrap();
+            conn.flush().unwrap();
+        }
+        client.set_nonblocking(true).unwrap();
+        while let Ok(read @ 1..) = client.read(&mut buf) {
+            received += read;
+        }
+
+        assert_eq!(received, data.len());
+    }
+
+    #[test]
+    fn gives_up_on_pending_packets_after_the_close_timeout() {
+        let (mut conn, _client) = Connection::for_test("");
+        conn.writer.write_unframed(&[0; 16]);
+        conn.disconnect(None);
+        assert!(conn.is_closing());
+
+        conn.closed_at = Some(Instant::now() - CLOSE_TIMEOUT);
+        assert!(!conn.is_closing());
+    }
+}
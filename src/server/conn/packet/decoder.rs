@@ -1,76 +1,277 @@
-use std::io;
+use std::io::{self, Read};
 
-use aes::cipher::KeyIvInit;
 use eyre::bail;
+use flate2::read::ZlibDecoder;
 
 use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::{PacketData, RawPacket};
 use crate::mc::types::VarInt;
-use crate::server::crypt::DecryptionStream;
+use crate::server::crypt::StreamDecryptor;
 
-pub enum PacketDecoder<R: io::Read> {
-    Raw(Option<R>),
-    Encrypted(Option<DecryptionStream<R>>),
-    Compressed { reader: DecryptionStream<R> },
+/// The maximum size of a frame, which is the most a 3 byte VarInt length can express.
+const MAX_FRAME_LENGTH: usize = 2097151;
+/// The maximum size of an uncompressed packet, as enforced by the vanilla server.
+const MAX_DATA_LENGTH: usize = 8388608;
+
+/// Decodes serverbound packets from received bytes through a stack of optional layers.
+///
+/// Received bytes are optionally decrypted and buffered. Complete frames are then split off the
+/// buffer, and optionally decompressed. Encryption and compression can be enabled independently.
+pub struct PacketDecoder {
+    buffer: Vec<u8>,
+    decryptor: Option<StreamDecryptor>,
+    compression_threshold: Option<u32>,
 }
 
-impl<R: io::Read> PacketDecoder<R> {
-    pub fn new(reader: R) -> Self {
-        Self::Raw(Some(reader))
+impl PacketDecoder {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new(), decryptor: None, compression_threshold: None }
     }
 
     pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> KeisteenResult<()> {
-        let reader = match self {
-            Self::Raw(reader) => reader.take().unwrap(),
-            Self::Encrypted(_) | Self::Compressed { .. } => bail!("encryption already enabled"),
-        };
+        if self.decryptor.is_some() {
+            bail!("encryption already enabled");
+        }
 
-        let cipher = cfb8::Decryptor::new_from_slices(shared_secret, shared_secret).unwrap();
-        let decryption_stream = DecryptionStream::new(cipher, reader);
-        *self = Self::Encrypted(Some(decryption_stream));
+        let mut decryptor = StreamDecryptor::new(shared_secret)?;
+        // Everything after the packet that enabled encryption has been received encrypted.
+        decryptor.decrypt(&mut self.buffer);
+        self.decryptor = Some(decryptor);
 
         Ok(())
     }
 
-    pub fn enable_compression(&mut self) -> KeisteenResult<()> {
-        let reader = match self {
-            Self::Raw(_) => bail!("stream is not encrypted"),
-            Self::Encrypted(reader) => reader.take().unwrap(),
-            Self::Compressed { .. } => bail!("compression already enabled"),
-        };
+    pub fn enable_compression(&mut self, threshold: u32) -> KeisteenResult<()> {
+        if self.compression_threshold.is_some() {
+            bail!("compression already enabled");
+        }
 
-        *self = Self::Compressed { reader };
+        self.compression_threshold = Some(threshold);
 
         Ok(())
     }
 
-    pub fn read_packet(&mut self) -> io::Result<RawPacket> {
-        fn read_uncompresed<R: io::Read>(mut reader: R, length: VarInt) -> io::Result<RawPacket> {
-            let packet_id = VarInt::from_reader(&mut reader)?;
-            let data_len = (length.raw() as usize).saturating_sub(packet_id.len());
-            let mut data = vec![0u8; data_len];
-            reader.read_exact(&mut data)?;
-            Ok(RawPacket { packet_id, data: PacketData::from(data) })
+    /// Returns the received bytes that have not been decoded into packets yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Discards received bytes that were handled before any packets could be decoded.
+    pub fn consume(&mut self, length: usize) {
+        self.buffer.drain(..length);
+    }
+
+    pub fn receive(&mut self, bytes: &mut [u8]) {
+        if let Some(decryptor) = &mut self.decryptor {
+            decryptor.decrypt(bytes);
         }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Decodes the next packet, or returns `None` if it has not been fully received yet.
+    pub fn next_packet(&mut self) -> io::Result<Option<RawPacket>> {
+        let Some(frame) = self.next_frame()? else { return Ok(None) };
 
-        let packet = match self {
-            PacketDecoder::Raw(Some(reader)) => {
-                let length = VarInt::from_reader(reader)?;
-                read_uncompresed(reader, length)?
-            }
-            PacketDecoder::Encrypted(Some(reader)) => {
-                let length = VarInt::from_reader(reader)?;
-                read_uncompresed(reader, length)?
-            }
-            PacketDecoder::Compressed { reader } => {
-                // TODO: Implement compression.
-
-                let length = VarInt::from_reader(reader)?;
-                read_uncompresed(reader, length)?
-            }
-            _ => unreachable!(),
+        let packet = match self.compression_threshold {
+            Some(threshold) => decompress_frame(frame, threshold)?,
+            None => frame,
         };
 
-        Ok(packet)
+        parse_packet(packet).map(Some)
+    }
+
+    fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut cursor = io::Cursor::new(&self.buffer);
+        let length = match VarInt::from_reader(&mut cursor) {
+            Ok(length) => length.raw(),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        if length < 0 || length as usize > MAX_FRAME_LENGTH {
+            return Err(invalid_data(format!("invalid packet length: {length}")));
+        }
+
+        let frame_start = cursor.position() as usize;
+        let frame_end = frame_start + length as usize;
+        if self.buffer.len() < frame_end {
+            return Ok(None);
+        }
+
+        let frame = self.buffer[frame_start..frame_end].to_vec();
+        self.buffer.drain(..frame_end);
+        Ok(Some(frame))
+    }
+}
+
+fn decompress_frame(frame: Vec<u8>, threshold: u32) -> io::Result<Vec<u8>> {
+    let mut cursor = io::Cursor::new(frame);
+    let data_length = VarInt::from_reader(&mut cursor)?.raw();
+    let body_start = cursor.position() as usize;
+    let mut frame = cursor.into_inner();
+
+    if data_length == 0 {
+        frame.drain(..body_start);
+        return Ok(frame);
+    }
+
+    if data_length < 0 || (data_length as u32) < threshold {
+        return Err(invalid_data(format!(
+            "badly compressed packet: size of {data_length} is below server threshold of {threshold}"
+        )));
+    }
+
+    if data_length as usize > MAX_DATA_LENGTH {
+        return Err(invalid_data(format!(
+            "badly compressed packet: size of {data_length} is larger than protocol maximum of {MAX_DATA_LENGTH}"
+        )));
+    }
+
+    // The output is limited to the announced length, so a small frame cannot expand to an
+    // arbitrary amount of memory.
+    let body = &frame[body_start..];
+    let mut decoder = ZlibDecoder::new(body);
+    let mut packet = Vec::with_capacity(data_length as usize);
+    (&mut decoder).take(data_length as u64).read_to_end(&mut packet)?;
+
+    if packet.len() != data_length as usize {
+        return Err(invalid_data(format!(
+            "badly compressed packet: expected {data_length} bytes, got {}",
+            packet.len()
+        )));
+    }
+
+    if decoder.read(&mut [0])? != 0 || decoder.total_in() as usize != body.len() {
+        return Err(invalid_data(format!(
+            "badly compressed packet: more data after {data_length} bytes"
+        )));
+    }
+
+    Ok(packet)
+}
+
+fn parse_packet(packet: Vec<u8>) -> io::Result<RawPacket> {
+    let mut cursor = io::Cursor::new(packet);
+    let packet_id = VarInt::from_reader(&mut cursor)?;
+    let data_start = cursor.position() as usize;
+    let mut data = cursor.into_inner();
+    data.drain(..data_start);
+    Ok(RawPacket { packet_id, data: PacketData::from(data) })
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+    use crate::server::conn::packet::encoder::PacketEncoder;
+
+    fn packet(length: usize) -> RawPacket {
+        let data: Vec<u8> = (0..length).map(|i| i as u8).collect();
+        RawPacket { packet_id: VarInt::new(0x2A), data: PacketData::from(data) }
+    }
+
+    fn round_trip(packet: RawPacket, compression_threshold: Option<u32>) -> RawPacket {
+        let mut encoder = PacketEncoder::new();
+        let mut decoder = PacketDecoder::new();
+        if let Some(threshold) = compression_threshold {
+            encoder.enable_compression(threshold, 6).unwrap();
+            decoder.enable_compression(threshold).unwrap();
+        }
+
+        encoder.write_packet(packet).unwrap();
+        decoder.receive(&mut encoder.pending().to_vec());
+        let decoded = decoder.next_packet().unwrap().expect("packet should be complete");
+        assert!(decoder.next_packet().unwrap().is_none());
+        decoded
+    }
+
+    fn compressed_frame(data_length: i32, body: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        VarInt::new(data_length).to_writer(&mut frame).unwrap();
+        frame.extend_from_slice(body);
+        frame
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn round_trips_uncompressed_packets() {
+        let decoded = round_trip(packet(100), None);
+        assert_eq!(decoded.packet_id.raw(), 0x2A);
+        assert_eq!(decoded.data.bytes(), packet(100).data.bytes());
+    }
+
+    #[test]
+    fn round_trips_packets_below_compression_threshold() {
+        let decoded = round_trip(packet(10), Some(256));
+        assert_eq!(decoded.packet_id.raw(), 0x2A);
+        assert_eq!(decoded.data.bytes(), packet(10).data.bytes());
+    }
+
+    #[test]
+    fn round_trips_packets_above_compression_threshold() {
+        let decoded = round_trip(packet(5000), Some(256));
+        assert_eq!(decoded.packet_id.raw(), 0x2A);
+        assert_eq!(decoded.data.bytes(), packet(5000).data.bytes());
+    }
+
+    #[test]
+    fn waits_for_complete_frames() {
+        let mut encoder = PacketEncoder::new();
+        encoder.write_packet(packet(100)).unwrap();
+        let mut bytes = encoder.pending().to_vec();
+
+        let mut decoder = PacketDecoder::new();
+        let (first, second) = bytes.split_at_mut(50);
+        decoder.receive(first);
+        assert!(decoder.next_packet().unwrap().is_none());
+        decoder.receive(second);
+        assert!(decoder.next_packet().unwrap().is_some());
+    }
+
+    #[test]
+    fn rejects_frames_longer_than_the_maximum() {
+        let mut decoder = PacketDecoder::new();
+        let mut bytes = Vec::new();
+        VarInt::new(MAX_FRAME_LENGTH as i32 + 1).to_writer(&mut bytes).unwrap();
+        decoder.receive(&mut bytes);
+        assert!(decoder.next_packet().is_err());
+    }
+
+    #[test]
+    fn rejects_packets_that_decompress_beyond_their_length() {
+        let frame = compressed_frame(300, &zlib(&vec![0; 1024 * 1024]));
+        assert!(decompress_frame(frame, 256).is_err());
+    }
+
+    #[test]
+    fn rejects_packets_that_decompress_below_their_length() {
+        let frame = compressed_frame(300, &zlib(&[0; 299]));
+        assert!(decompress_frame(frame, 256).is_err());
+    }
+
+    #[test]
+    fn rejects_data_after_the_compressed_packet() {
+        let mut body = zlib(&[0; 300]);
+        body.extend_from_slice(&[1, 2, 3]);
+        assert!(decompress_frame(compressed_frame(300, &body), 256).is_err());
+    }
+
+    #[test]
+    fn accepts_packets_of_exactly_their_length() {
+        let frame = compressed_frame(300, &zlib(&[7; 300]));
+        assert_eq!(decompress_frame(frame, 256).unwrap(), vec![7; 300]);
     }
 }
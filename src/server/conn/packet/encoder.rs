@@ -1,83 +1,119 @@
-use std::io;
+use std::io::{self, Write};
 
-use aes::cipher::KeyIvInit;
 use eyre::bail;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
 
 use crate::error::KeisteenResult;
-use crate::mc::protocol::packet::PacketData;
-use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::protocol::packet::RawPacket;
 use crate::mc::types::VarInt;
-use crate::server::crypt::EncryptionStream;
+use crate::server::crypt::StreamEncryptor;
 
-pub enum PacketEncoder<W: io::Write> {
-    Raw(Option<W>),
-    Encrypted(Option<EncryptionStream<W>>),
-    Compressed { writer: EncryptionStream<W>, threshold: u32, level: u32 },
+/// Encodes clientbound packets into a buffer through a stack of optional layers.
+///
+/// Packets are first framed, optionally compressed, and then optionally encrypted before being
+/// appended to the outgoing buffer. Encryption and compression can be enabled independently.
+pub struct PacketEncoder {
+    buffer: Vec<u8>,
+    encryptor: Option<StreamEncryptor>,
+    compression: Option<CompressionSettings>,
 }
 
-impl<W: io::Write> PacketEncoder<W> {
-    pub fn new(writer: W) -> Self {
-        Self::Raw(Some(writer))
+#[derive(Debug, Clone, Copy)]
+struct CompressionSettings {
+    threshold: u32,
+    level: u32,
+}
+
+impl PacketEncoder {
+    pub fn new() -> Self {
+        Self { buffer: Vec::new(), encryptor: None, compression: None }
     }
 
     pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> KeisteenResult<()> {
-        let writer = match self {
-            Self::Raw(writer) => writer.take().unwrap(),
-            Self::Encrypted(_) | Self::Compressed { .. } => bail!("encryption already enabled"),
-        };
+        if self.encryptor.is_some() {
+            bail!("encryption already enabled");
+        }
 
-        let cipher = cfb8::Encryptor::new_from_slices(shared_secret, shared_secret).unwrap();
-        let encryption_stream = EncryptionStream::new(cipher, writer);
-        *self = Self::Encrypted(Some(encryption_stream));
+        self.encryptor = Some(StreamEncryptor::new(shared_secret)?);
 
         Ok(())
     }
 
     pub fn enable_compression(&mut self, threshold: u32, level: u32) -> KeisteenResult<()> {
-        let writer = match self {
-            Self::Raw(_) => bail!("stream is not encrypted"),
-            Self::Encrypted(writer) => writer.take().unwrap(),
-            Self::Compressed { .. } => bail!("compression already enabled"),
-        };
+        if self.compression.is_some() {
+            bail!("compression already enabled");
+        }
 
-        *self = Self::Compressed { writer, threshold, level };
+        self.compression = Some(CompressionSettings { threshold, level });
 
         Ok(())
     }
 
-    pub fn write_packet<P: ClientboundPacket>(&mut self, packet: P) -> io::Result<()> {
-        let packet_id = VarInt::new(P::PACKET_ID);
-        let mut data = PacketData::new();
-        packet.encode(&mut data);
-        let packet_length = VarInt::new((packet_id.len() + data.bytes().len()) as i32);
+    /// Returns the encoded bytes that have not been written to the client yet.
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Removes `count` bytes from the start of the pending bytes, after they have been written.
+    pub fn advance(&mut self, count: usize) {
+        self.buffer.drain(..count);
+    }
 
+    /// Appends bytes to the pending bytes without framing, compressing or encrypting them.
+    pub fn write_unframed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn write_packet(&mut self, packet: RawPacket) -> io::Result<()> {
         let mut buf = Vec::new();
-        packet_id.to_writer(&mut buf)?;
-        data.to_writer(&mut buf)?;
-
-        match self {
-            PacketEncoder::Raw(Some(writer)) => {
-                packet_length.to_writer(writer)?;
-                packet_id.to_writer(writer)?;
-                data.to_writer(writer)?;
-            }
-            PacketEncoder::Encrypted(Some(writer)) => {
-                packet_length.to_writer(writer)?;
-                packet_id.to_writer(writer)?;
-                data.to_writer(writer)?;
-            }
-            PacketEncoder::Compressed { writer, threshold, level } => {
-                // TODO: Implement compression.
-                let _ = threshold;
-                let _ = level;
-
-                packet_length.to_writer(writer)?;
-                packet_id.to_writer(writer)?;
-                data.to_writer(writer)?;
+        packet.packet_id.to_writer(&mut buf)?;
+        packet.data.to_writer(&mut buf)?;
+
+        let mut frame = match self.compression {
+            Some(CompressionSettings { threshold, level }) => {
+                frame_compressed(buf, threshold, level)?
             }
-            _ => unreachable!(),
+            None => frame_uncompressed(buf)?,
+        };
+
+        if let Some(encryptor) = &mut self.encryptor {
+            encryptor.encrypt(&mut frame);
         }
 
+        self.buffer.extend_from_slice(&frame);
+
         Ok(())
     }
 }
+
+/// Frames a packet as `Length | Packet ID | Data`.
+fn frame_uncompressed(packet: Vec<u8>) -> io::Result<Vec<u8>> {
+    let packet_length = VarInt::new(packet.len() as i32);
+
+    let mut frame = Vec::with_capacity(packet_length.len() + packet.len());
+    packet_length.to_writer(&mut frame)?;
+    frame.extend_from_slice(&packet);
+    Ok(frame)
+}
+
+/// Frames a packet as `Packet Length | Data Length | Packet ID | Data`.
+///
+/// Packets smaller than the threshold are sent uncompressed with a data length of 0.
+fn frame_compressed(packet: Vec<u8>, threshold: u32, level: u32) -> io::Result<Vec<u8>> {
+    let (data_length, body) = if packet.len() >= threshold as usize {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::new(level));
+        encoder.write_all(&packet)?;
+        (VarInt::new(packet.len() as i32), encoder.finish()?)
+    } else {
+        (VarInt::new(0), packet)
+    };
+
+    let packet_length = VarInt::new((data_length.len() + body.len()) as i32);
+
+    let mut frame = Vec::with_capacity(packet_length.len() + data_length.len() + body.len());
+    packet_length.to_writer(&mut frame)?;
+    data_length.to_writer(&mut frame)?;
+    frame.extend_from_slice(&body);
+    Ok(frame)
+}
@@ -10,7 +10,6 @@ impl Connection {
         match self.state {
             ConnectionState::Handshaking => server::handshake::handle_raw_packet(raw, self)?,
             ConnectionState::Status => server::status::handle_raw_packet(raw, self)?,
-            ConnectionState::Transfer => todo!(),
             ConnectionState::Login => server::login::handle_raw_packet(raw, self)?,
             ConnectionState::Config => server::config::handle_raw_packet(raw, self)?,
             ConnectionState::Play => server::play::handle_raw_packet(raw, self)?,
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use eyre::bail;
+
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::{PacketData, client};
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::Identifier;
+use crate::server::conn::{Connection, ConnectionState};
+
+/// The maximum size of a plugin message sent by a client, in bytes.
+pub const MAX_PLUGIN_MESSAGE_SIZE: usize = 32767;
+
+/// The maximum number of channels a client may register, like other servers allow.
+pub const MAX_CLIENT_CHANNELS: usize = 128;
+
+impl Connection {
+    /// The brand the client sent on `minecraft:brand`, e.g. `vanilla`.
+    pub fn client_brand(&self) -> Option<&str> {
+        self.client_brand.as_deref()
+    }
+
+    /// The channels the client has registered with `minecraft:register`.
+    pub fn client_channels(&self) -> &HashSet<Identifier> {
+        &self.client_channels
+    }
+
+    /// Sends a plugin message to the client. Only possible in the configuration and play states.
+    pub fn send_plugin_message(
+        &mut self,
+        channel: Identifier,
+        data: Vec<u8>,
+    ) -> KeisteenResult<()> {
+        match self.state {
+            ConnectionState::Config => {
+                self.send_packet(client::config::PluginMessage { channel, data })?
+            }
+            ConnectionState::Play => {
+                self.send_packet(client::play::PluginMessage { channel, data })?
+            }
+            state => bail!("cannot send plugin messages in {state:?} state"),
+        }
+
+        Ok(())
+    }
+
+    /// Tells the client which channels the server listens on.
+    pub(crate) fn announce_channels(&mut self) -> KeisteenResult<()> {
+        let channels = self.server.read(|server| {
+            server.channel_registry().channels().map(ToString::to_string).collect::<Vec<_>>()
+        });
+
+        if channels.is_empty() {
+            return Ok(());
+        }
+
+        self.send_plugin_message(
+            Identifier::new("minecraft", "register")?,
+            channels.join("\0").into_bytes(),
+        )
+    }
+
+    pub(crate) fn receive_plugin_message(
+        &mut self,
+        channel: Identifier,
+        data: Vec<u8>,
+    ) -> KeisteenResult<()> {
+        match (channel.namespace(), channel.value()) {
+            ("minecraft", "brand") => {
+                let brand: String = PacketData::from(data).read()?;
+                log::debug!("client brand: \"{brand}\"");
+                self.client_brand = Some(brand);
+            }
+            ("minecraft", "register") => {
+                for channel in parse_channels(&data) {
+                    log::debug!("client registered channel '{channel}'");
+                    self.client_channels.insert(channel);
+                }
+
+                if self.client_channels.len() > MAX_CLIENT_CHANNELS {
+                    log::warn!(
+                        "{} registered more than {MAX_CLIENT_CHANNELS} channels",
+                        self.remote_address
+                    );
+                    self.disconnect(Some(TextComponent::text("Too many channels registered")));
+                }
+            }
+            ("minecraft", "unregister") => {
+                for channel in parse_channels(&data) {
+                    log::debug!("client unregistered channel '{channel}'");
+                    self.client_channels.remove(&channel);
+                }
+            }
+            _ => {
+                let handler =
+                    self.server.read(|server| server.channel_registry().handler(&channel));
+                match handler {
+                    Some(handler) => handler(self, &data)?,
+                    None => log::debug!("received message on unregistered channel '{channel}'"),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the plugin message data that follows the channel, which spans the rest of the packet.
+pub(crate) fn read_plugin_message_data(data: &mut PacketData) -> KeisteenResult<Vec<u8>> {
+    let length = data.bytes().len();
+    if length > MAX_PLUGIN_MESSAGE_SIZE {
+        bail!("plugin message of {length} bytes exceeds {MAX_PLUGIN_MESSAGE_SIZE} bytes");
+    }
+
+    data.read_predefined(length)
+}
+
+/// Parses the NUL separated channel names of `minecraft:register` and `minecraft:unregister`.
+fn parse_channels(data: &[u8]) -> Vec<Identifier> {
+    data.split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| match str::from_utf8(name).map(str::parse::<Identifier>) {
+            Ok(Ok(channel)) => Some(channel),
+            _ => {
+                log::debug!("ignoring invalid channel name {:?}", String::from_utf8_lossy(name));
+                None
+            }
+        })
+        .collect()
+}
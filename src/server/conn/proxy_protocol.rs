@@ -0,0 +1,232 @@
+// Header sent by load balancers such as HAProxy in front of the proxied stream, announcing the
+// address of the client.
+// https://www.haproxy.org/download/3.0/doc/proxy-protocol.txt
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use eyre::{Context, ContextCompat, bail};
+
+use crate::error::KeisteenResult;
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V1_MAX_LENGTH: usize = 107;
+
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LENGTH: usize = 16;
+const V2_VERSION: u8 = 0x2;
+const V2_COMMAND_LOCAL: u8 = 0x0;
+const V2_COMMAND_PROXY: u8 = 0x1;
+const V2_FAMILY_INET: u8 = 0x1;
+const V2_FAMILY_INET6: u8 = 0x2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyHeader {
+    /// The address of the client, or `None` if the proxy did not forward one, e.g. for its own
+    /// health checks.
+    pub source: Option<SocketAddr>,
+    /// The length of the header in bytes, which precede the proxied stream.
+    pub length: usize,
+}
+
+impl ProxyHeader {
+    /// Parses a version 1 (text) or version 2 (binary) header from the first bytes that were
+    /// received on a fresh connection, or returns `None` if it has not been fully received yet.
+    pub fn parse(first_bytes: &[u8]) -> KeisteenResult<Option<Self>> {
+        if starts_with(first_bytes, V1_PREFIX) {
+            Self::parse_v1(first_bytes)
+        } else if starts_with(first_bytes, V2_SIGNATURE) {
+            Self::parse_v2(first_bytes)
+        } else {
+            bail!("connection does not start with a PROXY protocol header");
+        }
+    }
+
+    fn parse_v1(bytes: &[u8]) -> KeisteenResult<Option<Self>> {
+        let searched = &bytes[..bytes.len().min(V1_MAX_LENGTH)];
+        let Some(end) = searched.windows(2).position(|window| window == b"\r\n") else {
+            if searched.len() == V1_MAX_LENGTH {
+                bail!("PROXY protocol header is too long");
+            }
+            return Ok(None);
+        };
+
+        let line = std::str::from_utf8(&bytes[..end]).wrap_err("invalid PROXY protocol header")?;
+        let mut fields = line.split(' ').skip(1);
+        let source = match fields.next() {
+            Some("TCP4" | "TCP6") => {
+                let (Some(source), Some(_), Some(source_port), Some(_)) =
+                    (fields.next(), fields.next(), fields.next(), fields.next())
+                else {
+                    bail!("incomplete PROXY protocol header");
+                };
+
+                let source: IpAddr = source.parse().wrap_err("invalid proxied source address")?;
+                let source_port: u16 =
+                    source_port.parse().wrap_err("invalid proxied source port")?;
+                Some(SocketAddr::new(source, source_port))
+            }
+            Some("UNKNOWN") => None,
+            protocol => bail!("unsupported PROXY protocol family: {protocol:?}"),
+        };
+
+        Ok(Some(Self { source, length: end + 2 }))
+    }
+
+    fn parse_v2(bytes: &[u8]) -> KeisteenResult<Option<Self>> {
+        if bytes.len() < V2_HEADER_LENGTH {
+            return Ok(None);
+        }
+
+        let version = bytes[12] >> 4;
+        if version != V2_VERSION {
+            bail!("unsupported PROXY protocol version: {version}");
+        }
+
+        let address_length = u16::from_be_bytes([bytes[14], bytes[15]]) as usize;
+        let length = V2_HEADER_LENGTH + address_length;
+        if bytes.len() < length {
+            return Ok(None);
+        }
+
+        let addresses = &bytes[V2_HEADER_LENGTH..length];
+        let source = match bytes[12] & 0x0F {
+            // Connections made by the proxy itself, e.g. health checks.
+            V2_COMMAND_LOCAL => None,
+            V2_COMMAND_PROXY => match bytes[13] >> 4 {
+                V2_FAMILY_INET => {
+                    let address: [u8; 4] = addresses
+                        .get(..4)
+                        .and_then(|address| address.try_into().ok())
+                        .wrap_err("incomplete PROXY protocol addresses")?;
+                    let port = read_port(addresses, 8)?;
+                    Some(SocketAddr::new(Ipv4Addr::from(address).into(), port))
+                }
+                V2_FAMILY_INET6 => {
+                    let address: [u8; 16] = addresses
+                        .get(..16)
+                        .and_then(|address| address.try_into().ok())
+                        .wrap_err("incomplete PROXY protocol addresses")?;
+                    let port = read_port(addresses, 32)?;
+                    Some(SocketAddr::new(Ipv6Addr::from(address).into(), port))
+                }
+                // Unix sockets and unspecified families carry no usable client address.
+                _ => None,
+            },
+            command => bail!("unsupported PROXY protocol command: {command}"),
+        };
+
+        Ok(Some(Self { source, length }))
+    }
+}
+
+/// Whether the bytes start with the prefix, or could still do so once more bytes are received.
+fn starts_with(bytes: &[u8], prefix: &[u8]) -> bool {
+    if bytes.len() < prefix.len() { prefix.starts_with(bytes) } else { bytes.starts_with(prefix) }
+}
+
+fn read_port(addresses: &[u8], offset: usize) -> KeisteenResult<u16> {
+    let port = addresses.get(offset..offset + 2).wrap_err("incomplete PROXY protocol addresses")?;
+    Ok(u16::from_be_bytes([port[0], port[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2_header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.push(V2_VERSION << 4 | command);
+        header.push(family << 4 | 0x1);
+        header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+        header.extend_from_slice(addresses);
+        header
+    }
+
+    fn address(address: &str) -> Option<SocketAddr> {
+        Some(address.parse().unwrap())
+    }
+
+    #[test]
+    fn parses_v1_tcp4() {
+        let header = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n";
+        let parsed = ProxyHeader::parse(header).unwrap();
+        assert_eq!(parsed, Some(ProxyHeader { source: address("192.0.2.1:56324"), length: 47 }));
+    }
+
+    #[test]
+    fn parses_v1_tcp6() {
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 25565\r\n";
+        let parsed = ProxyHeader::parse(header).unwrap();
+        let source = address("[2001:db8::1]:56324");
+        assert_eq!(parsed, Some(ProxyHeader { source, length: header.len() }));
+    }
+
+    #[test]
+    fn parses_v1_unknown() {
+        let header = b"PROXY UNKNOWN\r\n";
+        let parsed = ProxyHeader::parse(header).unwrap();
+        assert_eq!(parsed, Some(ProxyHeader { source: None, length: header.len() }));
+    }
+
+    #[test]
+    fn parses_v2_proxy() {
+        let addresses = [192, 0, 2, 1, 198, 51, 100, 1, 0xDC, 0x04, 0x63, 0xDD];
+        let header = v2_header(V2_COMMAND_PROXY, V2_FAMILY_INET, &addresses);
+        let parsed = ProxyHeader::parse(&header).unwrap();
+        assert_eq!(parsed, Some(ProxyHeader { source: address("192.0.2.1:56324"), length: 28 }));
+
+        let mut addresses = [0; 36];
+        addresses[..16].copy_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        addresses[32..34].copy_from_slice(&56324u16.to_be_bytes());
+        let header = v2_header(V2_COMMAND_PROXY, V2_FAMILY_INET6, &addresses);
+        let parsed = ProxyHeader::parse(&header).unwrap();
+        let source = address("[2001:db8::1]:56324");
+        assert_eq!(parsed, Some(ProxyHeader { source, length: 52 }));
+    }
+
+    #[test]
+    fn parses_v2_local() {
+        let header = v2_header(V2_COMMAND_LOCAL, 0x0, &[]);
+        let parsed = ProxyHeader::parse(&header).unwrap();
+        assert_eq!(parsed, Some(ProxyHeader { source: None, length: V2_HEADER_LENGTH }));
+    }
+
+    #[test]
+    fn waits_for_truncated_headers() {
+        let v1 = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n";
+        let v2 =
+            v2_header(V2_COMMAND_PROXY, V2_FAMILY_INET, &[192, 0, 2, 1, 0, 0, 0, 0, 0, 1, 0, 2]);
+        for header in [&v1[..], &v2[..]] {
+            for length in 0..header.len() {
+                assert_eq!(ProxyHeader::parse(&header[..length]).unwrap(), None, "length {length}");
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_the_proxied_stream_after_the_header() {
+        let mut bytes = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 25565\r\n".to_vec();
+        bytes.extend_from_slice(b"\x10\x00garbage\r\n");
+        let parsed = ProxyHeader::parse(&bytes).unwrap().unwrap();
+        assert_eq!(parsed.length, 47);
+
+        let mut bytes = v2_header(V2_COMMAND_LOCAL, 0x0, &[]);
+        bytes.extend_from_slice(b"PROXY UNKNOWN\r\n");
+        let parsed = ProxyHeader::parse(&bytes).unwrap().unwrap();
+        assert_eq!(parsed.length, V2_HEADER_LENGTH);
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert!(ProxyHeader::parse(b"\x10\x00\xFF\x05").is_err());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 192.0.2.1\r\n").is_err());
+        assert!(ProxyHeader::parse(b"PROXY TCP4 not-an-ip 198.51.100.1 1 2\r\n").is_err());
+        assert!(ProxyHeader::parse(b"PROXY UDP4 192.0.2.1 198.51.100.1 1 2\r\n").is_err());
+        assert!(ProxyHeader::parse(&[b"PROXY TCP4 ".as_slice(), &[b'1'; 120]].concat()).is_err());
+
+        let mut header = v2_header(V2_COMMAND_PROXY, V2_FAMILY_INET, &[192, 0, 2, 1]);
+        assert!(ProxyHeader::parse(&header).is_err());
+        header[12] = 0x11;
+        assert!(ProxyHeader::parse(&header).is_err());
+    }
+}
@@ -1,13 +1,14 @@
-use std::io;
-
-use aes::cipher::generic_array::GenericArray;
-use aes::cipher::{BlockDecryptMut, BlockEncryptMut, BlockSizeUser};
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, BlockSizeUser, KeyIvInit};
+use eyre::bail;
 use rsa::traits::PublicKeyParts;
 use rsa::{Pkcs1v15Encrypt, RsaPrivateKey};
+use sha1::{Digest, Sha1};
 
+use crate::error::KeisteenResult;
 use crate::mc::protocol::packet::client;
 
 pub struct CryptKeys {
+    server_id: String,
     public_key_der: Vec<u8>,
     private_key: rsa::RsaPrivateKey,
 
@@ -18,6 +19,8 @@ impl CryptKeys {
     pub fn new() -> Self {
         let private_key = Self::generate_private_key();
         Self {
+            // Vanilla servers have been sending an empty server id since 1.7.
+            server_id: String::new(),
             public_key_der: rsa_der::public_key_to_der(
                 &private_key.n().to_bytes_be(),
                 &private_key.e().to_bytes_be(),
@@ -37,6 +40,18 @@ impl CryptKeys {
         Ok(verify_token == self.verification_token)
     }
 
+    /// Computes the server hash that is sent to the session service to authenticate a player.
+    ///
+    /// This is the SHA-1 digest of the server id, the shared secret and the public key, formatted
+    /// as a signed two's complement hexadecimal number.
+    pub fn server_hash(&self, shared_secret: &[u8]) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(self.server_id.as_bytes());
+        hasher.update(shared_secret);
+        hasher.update(&self.public_key_der);
+        signed_hex_digest(hasher.finalize().into())
+    }
+
     fn generate_private_key() -> RsaPrivateKey {
         let mut rng = rand::thread_rng();
         rsa::RsaPrivateKey::new(&mut rng, 1024).expect("failed to generate a key")
@@ -47,7 +62,7 @@ impl CryptKeys {
         should_authenticate: bool,
     ) -> client::login::EncryptionRequest {
         client::login::EncryptionRequest {
-            server_id: "".to_string(),
+            server_id: self.server_id.clone(),
             public_key: self.public_key_der.clone(),
             verify_token: self.verification_token.to_vec(),
             should_authenticate,
@@ -55,58 +70,84 @@ impl CryptKeys {
     }
 }
 
-pub struct EncryptionStream<W: io::Write> {
+fn signed_hex_digest(mut digest: [u8; 20]) -> String {
+    let is_negative = digest[0] & 0x80 != 0;
+    if is_negative {
+        let mut carry = true;
+        for byte in digest.iter_mut().rev() {
+            let (value, overflow) = (!*byte).overflowing_add(carry as u8);
+            *byte = value;
+            carry = overflow;
+        }
+    }
+
+    let hex = digest.iter().map(|byte| format!("{byte:02x}")).collect::<String>();
+    let hex = hex.trim_start_matches('0');
+    let hex = if hex.is_empty() { "0" } else { hex };
+
+    if is_negative { format!("-{hex}") } else { hex.to_string() }
+}
+
+pub struct StreamEncryptor {
     cipher: cfb8::Encryptor<aes::Aes128>,
-    writer: W,
 }
 
-impl<W: io::Write> EncryptionStream<W> {
-    pub fn new(cipher: cfb8::Encryptor<aes::Aes128>, writer: W) -> Self {
-        Self { cipher, writer }
+impl StreamEncryptor {
+    pub fn new(shared_secret: &[u8]) -> KeisteenResult<Self> {
+        let Ok(cipher) = cfb8::Encryptor::new_from_slices(shared_secret, shared_secret) else {
+            bail!("shared secret should be 16 bytes, got {}", shared_secret.len());
+        };
+        Ok(Self { cipher })
     }
-}
 
-impl<W: io::Write> io::Write for EncryptionStream<W> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    pub fn encrypt(&mut self, buf: &mut [u8]) {
         let block_size = cfb8::Encryptor::<aes::Aes128>::block_size();
-        let mut written_bytes = 0;
-        for block in buf.chunks(block_size) {
-            let mut out = [0u8];
-
-            let out_block = GenericArray::from_mut_slice(&mut out);
-            self.cipher.encrypt_block_b2b_mut(block.into(), out_block);
-
-            let written = self.writer.write(&out)?;
-            written_bytes += written;
+        for block in buf.chunks_mut(block_size) {
+            self.cipher.encrypt_block_mut(block.into());
         }
-        Ok(written_bytes)
-    }
-
-    fn flush(&mut self) -> io::Result<()> {
-        self.writer.flush()
     }
 }
 
-pub struct DecryptionStream<R: io::Read> {
+pub struct StreamDecryptor {
     cipher: cfb8::Decryptor<aes::Aes128>,
-    reader: R,
 }
 
-impl<R: io::Read> DecryptionStream<R> {
-    pub fn new(cipher: cfb8::Decryptor<aes::Aes128>, reader: R) -> Self {
-        Self { cipher, reader }
+impl StreamDecryptor {
+    pub fn new(shared_secret: &[u8]) -> KeisteenResult<Self> {
+        let Ok(cipher) = cfb8::Decryptor::new_from_slices(shared_secret, shared_secret) else {
+            bail!("shared secret should be 16 bytes, got {}", shared_secret.len());
+        };
+        Ok(Self { cipher })
     }
-}
 
-impl<R: io::Read> io::Read for DecryptionStream<R> {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    pub fn decrypt(&mut self, buf: &mut [u8]) {
         let block_size = cfb8::Decryptor::<aes::Aes128>::block_size();
-        let mut bytes_read = 0;
         for block in buf.chunks_mut(block_size) {
-            let read = self.reader.read(block)?;
             self.cipher.decrypt_block_mut(block.into());
-            bytes_read += read;
         }
-        Ok(bytes_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_of(name: &str) -> String {
+        signed_hex_digest(Sha1::digest(name.as_bytes()).into())
+    }
+
+    #[test]
+    fn formats_positive_digests() {
+        assert_eq!(digest_of("Notch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+    }
+
+    #[test]
+    fn formats_negative_digests_in_twos_complement() {
+        assert_eq!(digest_of("jeb_"), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+    }
+
+    #[test]
+    fn strips_leading_zeros() {
+        assert_eq!(digest_of("simon"), "88e16a1019277b15d58faf0541e11910eb756f6");
     }
 }
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use eyre::bail;
+
+use crate::error::KeisteenResult;
+
+const FILE_NAME: &str = "server-icon.png";
+const SIZE: u32 = 64;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+// The IHDR chunk always comes first, right after its length and type.
+const PNG_IHDR_TYPE: &[u8] = b"IHDR";
+const PNG_HEADER_LENGTH: usize = 24;
+
+/// The icon shown next to the server in the server list, loaded from `server-icon.png` in the
+/// server folder.
+#[derive(Debug)]
+pub(crate) struct Favicon {
+    path: PathBuf,
+    modified_at: Option<SystemTime>,
+    data_uri: Option<String>,
+}
+
+impl Favicon {
+    pub(crate) fn load(server_folder_path: &Path) -> Self {
+        Self::load_file(server_folder_path.join(FILE_NAME))
+    }
+
+    fn load_file(path: PathBuf) -> Self {
+        let modified_at = modified_at(&path);
+        let mut favicon = Self { path, modified_at, data_uri: None };
+
+        if modified_at.is_none() {
+            return favicon;
+        }
+
+        match read(&favicon.path) {
+            Ok(data_uri) => {
+                log::info!("loaded server icon from {}", favicon.path.display());
+                favicon.data_uri = Some(data_uri);
+            }
+            Err(err) => {
+                log::warn!("ignoring invalid server icon {}: {err}", favicon.path.display());
+            }
+        }
+
+        favicon
+    }
+
+    /// The icon as a `data:` URI, as sent in the status response.
+    pub(crate) fn data_uri(&self) -> Option<&str> {
+        self.data_uri.as_deref()
+    }
+
+    /// Loads the icon again if the file was changed, added or removed since this icon was loaded.
+    /// This reads the file, so it should not be called while holding the server lock.
+    pub(crate) fn reload_if_changed(&self) -> Option<Self> {
+        (modified_at(&self.path) != self.modified_at).then(|| Self::load_file(self.path.clone()))
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn read(path: &Path) -> KeisteenResult<String> {
+    encode(&std::fs::read(path)?)
+}
+
+fn encode(image: &[u8]) -> KeisteenResult<String> {
+    let (width, height) = png_size(image)?;
+    if width != SIZE || height != SIZE {
+        bail!("icon must be {SIZE}x{SIZE} pixels, but is {width}x{height}");
+    }
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(image)))
+}
+
+fn png_size(image: &[u8]) -> KeisteenResult<(u32, u32)> {
+    if image.len() < PNG_HEADER_LENGTH
+        || !image.starts_with(PNG_SIGNATURE)
+        || &image[12..16] != PNG_IHDR_TYPE
+    {
+        bail!("icon is not a PNG image");
+    }
+
+    let width = u32::from_be_bytes(image[16..20].try_into().expect("slice has length 4"));
+    let height = u32::from_be_bytes(image[20..24].try_into().expect("slice has length 4"));
+
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The start of a PNG image of the given size, which is all the validation looks at.
+    fn png_header(width: u32, height: u32) -> Vec<u8> {
+        let mut image = PNG_SIGNATURE.to_vec();
+        image.extend_from_slice(&13u32.to_be_bytes());
+        image.extend_from_slice(PNG_IHDR_TYPE);
+        image.extend_from_slice(&width.to_be_bytes());
+        image.extend_from_slice(&height.to_be_bytes());
+        image
+    }
+
+    #[test]
+    fn encodes_64x64_png_images() {
+        let image = png_header(64, 64);
+        assert_eq!(
+            encode(&image).unwrap(),
+            format!("data:image/png;base64,{}", STANDARD.encode(&image))
+        );
+    }
+
+    #[test]
+    fn rejects_images_of_other_sizes() {
+        let err = encode(&png_header(128, 64)).unwrap_err();
+        assert_eq!(err.to_string(), "icon must be 64x64 pixels, but is 128x64");
+    }
+
+    #[test]
+    fn rejects_images_that_are_not_png() {
+        let not_png = [
+            b"\xff\xd8\xff\xe0 a jpeg image".to_vec(),
+            png_header(64, 64)[..PNG_HEADER_LENGTH - 1].to_vec(),
+            [&png_header(64, 64)[..12], b"IDAT", &[0; 8]].concat(),
+        ];
+        for image in not_png {
+            assert_eq!(encode(&image).unwrap_err().to_string(), "icon is not a PNG image");
+        }
+    }
+
+    #[test]
+    fn reloads_the_icon_when_the_file_changes() {
+        let path = std::env::temp_dir().join(format!("keisteen-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+
+        let favicon = Favicon::load(&path);
+        assert_eq!(favicon.data_uri(), None);
+        assert!(favicon.reload_if_changed().is_none());
+
+        std::fs::write(path.join(FILE_NAME), png_header(64, 64)).unwrap();
+        let favicon = favicon.reload_if_changed().expect("icon was added");
+        assert!(favicon.data_uri().is_some());
+        assert!(favicon.reload_if_changed().is_none());
+
+        std::fs::remove_dir_all(&path).unwrap();
+        let favicon = favicon.reload_if_changed().expect("icon was removed");
+        assert_eq!(favicon.data_uri(), None);
+    }
+}
@@ -1,9 +1,10 @@
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
-use eyre::Context;
+use eyre::{Context, bail};
 
 use crate::error::KeisteenResult;
+use crate::server::auth::HttpSessionService;
 
 #[derive(Debug, Clone)]
 pub struct ServerFolder {
@@ -36,6 +37,11 @@ pub struct ServerConfig {
     properties: ServerProperties,
 
     enforces_secure_chat: bool,
+    forwarding_mode: ForwardingMode,
+    forwarding_secret: String,
+    network_compression_level: u32,
+    proxy_protocol: bool,
+    session_service_url: String,
 }
 
 impl ServerConfig {
@@ -55,6 +61,18 @@ impl ServerConfig {
             Self::default()
         };
 
+        if config.forwarding_mode == ForwardingMode::Velocity && config.forwarding_secret.is_empty()
+        {
+            bail!("velocity forwarding requires a forwarding secret");
+        }
+
+        if config.network_compression_level > 9 {
+            bail!(
+                "network compression level must be between 0 and 9, got {}",
+                config.network_compression_level
+            );
+        }
+
         Ok(config)
     }
 
@@ -65,21 +83,73 @@ impl ServerConfig {
     pub fn enforces_secure_chat(&self) -> bool {
         self.enforces_secure_chat
     }
+
+    pub fn forwarding_mode(&self) -> ForwardingMode {
+        self.forwarding_mode
+    }
+
+    /// The secret shared with the proxy, used to verify forwarded player information.
+    pub fn forwarding_secret(&self) -> &str {
+        &self.forwarding_secret
+    }
+
+    pub fn network_compression_level(&self) -> u32 {
+        self.network_compression_level
+    }
+
+    /// Whether connections start with a PROXY protocol header announcing the client address, as
+    /// sent by load balancers such as HAProxy.
+    pub fn proxy_protocol(&self) -> bool {
+        self.proxy_protocol
+    }
+
+    pub fn session_service_url(&self) -> &str {
+        &self.session_service_url
+    }
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
-        Self { properties: ServerProperties::default(), enforces_secure_chat: true }
+        Self {
+            properties: ServerProperties::default(),
+            enforces_secure_chat: true,
+            forwarding_mode: ForwardingMode::None,
+            forwarding_secret: String::new(),
+            network_compression_level: 3,
+            proxy_protocol: false,
+            session_service_url: HttpSessionService::MOJANG_URL.to_string(),
+        }
     }
 }
 
+/// How a proxy in front of the server forwards information about the connecting players.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardingMode {
+    /// Players connect directly to the server.
+    None,
+    /// BungeeCord's legacy forwarding, which appends the player information to the server address
+    /// of the handshake. It is not signed, so the server must only be reachable through the proxy.
+    #[serde(rename = "bungeecord")]
+    BungeeCord,
+    /// Velocity's modern forwarding, signed with the forwarding secret.
+    Velocity,
+}
+
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default)]
 pub struct ServerProperties {
+    pub accepts_transfers: bool,
+    pub enable_status: bool,
     pub hardcore: bool,
+    pub hide_online_players: bool,
     pub max_players: i32,
+    pub max_tick_time: i64,
     pub motd: String,
+    pub network_compression_threshold: i32,
+    pub online_mode: bool,
     pub server_ip: Ipv4Addr,
     pub server_port: u16,
 }
@@ -87,9 +157,15 @@ pub struct ServerProperties {
 impl Default for ServerProperties {
     fn default() -> Self {
         Self {
+            accepts_transfers: false,
+            enable_status: true,
             hardcore: false,
+            hide_online_players: false,
             max_players: 20,
+            max_tick_time: 60000,
             motd: "A Keisteen Minecraft Server".to_string(),
+            network_compression_threshold: 256,
+            online_mode: true,
             server_ip: Ipv4Addr::UNSPECIFIED,
             server_port: 25565,
         }
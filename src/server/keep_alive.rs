@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use crate::mc::text::text_component::TextComponent;
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Tracks the keep-alive packets exchanged with a client in the configuration and play states.
+#[derive(Debug)]
+pub(crate) struct KeepAlive {
+    last_sent_at: Instant,
+    pending: Option<(i64, Instant)>,
+    latency: Duration,
+    has_timed_out: bool,
+}
+
+#[derive(Debug)]
+pub(crate) enum KeepAliveTick {
+    Idle,
+    Send(i64),
+    TimedOut,
+}
+
+impl KeepAlive {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_sent_at: Instant::now(),
+            pending: None,
+            latency: Duration::ZERO,
+            has_timed_out: false,
+        }
+    }
+
+    /// The reason clients are disconnected with when they fail to answer a keep-alive.
+    pub(crate) fn timeout_reason() -> TextComponent {
+        TextComponent::translatable("disconnect.timeout", vec![])
+    }
+
+    pub(crate) fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    pub(crate) fn tick(&mut self) -> KeepAliveTick {
+        let now = Instant::now();
+        if self.has_timed_out || now - self.last_sent_at < KEEP_ALIVE_INTERVAL {
+            return KeepAliveTick::Idle;
+        }
+
+        if self.pending.is_some() {
+            self.has_timed_out = true;
+            return KeepAliveTick::TimedOut;
+        }
+
+        let id = rand::random();
+        self.last_sent_at = now;
+        self.pending = Some((id, now));
+
+        KeepAliveTick::Send(id)
+    }
+
+    /// Handles a keep-alive echoed by the client. Returns `false` if it was not expected.
+    pub(crate) fn receive(&mut self, id: i64) -> bool {
+        match self.pending {
+            Some((pending_id, sent_at)) if pending_id == id => {
+                self.latency = (self.latency * 3 + sent_at.elapsed()) / 4;
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,37 +1,80 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, PoisonError, RwLock};
 
+use crate::mc::protocol::packet::client::{self, ClientboundPacket};
 use crate::mc::protocol::registry::Registries;
+use crate::mc::text::text_component::TextComponent;
+use crate::mc::types::VarInt;
+use crate::server::auth::{HttpSessionService, SessionService};
+use crate::server::channel::ChannelRegistry;
+use crate::server::conn::ConnectionId;
 use crate::server::crypt::CryptKeys;
+use crate::server::favicon::Favicon;
 use crate::server::folder::ServerFolder;
+use crate::server::keep_alive::{KeepAlive, KeepAliveTick};
 use crate::server::player_list::PlayerList;
+use crate::server::status::{DefaultStatusProvider, ServerStatus, StatusProvider};
+use crate::server::tick::{Outbox, TickTimes};
+use crate::server::translations::Translations;
 
+pub mod auth;
+pub mod channel;
+pub mod client_settings;
 pub mod conn;
 pub mod entity;
 pub mod folder;
+pub mod keep_alive;
 pub mod player;
 pub mod player_list;
 pub mod player_profile;
+pub mod status;
+pub mod tick;
+pub mod translations;
+pub mod watchdog;
 
 mod crypt;
+mod favicon;
 
 pub struct Server {
     server_folder: ServerFolder,
+    // Shared so that the icon can be reloaded without holding the server lock.
+    favicon: Arc<Favicon>,
+    translations: Translations,
 
     crypt_keys: CryptKeys,
     registries: Registries,
     player_list: PlayerList,
+
+    session_service: Arc<dyn SessionService>,
+    status_provider: Arc<dyn StatusProvider>,
+    channel_registry: ChannelRegistry,
+
+    current_tick: u64,
+    tick_times: TickTimes,
+    outbox: Outbox,
 }
 
 impl Server {
     pub fn new(server_folder: ServerFolder) -> Self {
         let max_players = server_folder.config().properties().max_players as i32;
+        let session_service =
+            Arc::new(HttpSessionService::new(server_folder.config().session_service_url()));
 
         Self {
+            favicon: Arc::new(Favicon::load(server_folder.path())),
+            translations: Translations::load(server_folder.path()),
             server_folder,
 
             crypt_keys: CryptKeys::new(),
             registries: Registries::load_from_assets(),
             player_list: PlayerList::new(max_players),
+
+            session_service,
+            status_provider: Arc::new(DefaultStatusProvider),
+            channel_registry: ChannelRegistry::default(),
+
+            current_tick: 0,
+            tick_times: TickTimes::new(),
+            outbox: Outbox::default(),
         }
     }
 
@@ -39,6 +82,20 @@ impl Server {
         &self.server_folder
     }
 
+    /// The server icon as a `data:` URI, if a valid `server-icon.png` exists.
+    pub fn favicon(&self) -> Option<&str> {
+        self.favicon.data_uri()
+    }
+
+    /// The translations from the `lang` directory of the server folder. Connections apply them to
+    /// the text they send with [Connection::localize], while packets queued with
+    /// [Server::send_packet] have to be localized with the player's locale beforehand.
+    ///
+    /// [Connection::localize]: crate::server::conn::Connection::localize
+    pub fn translations(&self) -> &Translations {
+        &self.translations
+    }
+
     pub fn crypt_keys(&self) -> &CryptKeys {
         &self.crypt_keys
     }
@@ -54,20 +111,107 @@ impl Server {
     pub fn player_list_mut(&mut self) -> &mut PlayerList {
         &mut self.player_list
     }
+
+    pub fn session_service(&self) -> Arc<dyn SessionService> {
+        Arc::clone(&self.session_service)
+    }
+
+    pub fn set_session_service<S: SessionService + 'static>(&mut self, session_service: S) {
+        self.session_service = Arc::new(session_service);
+    }
+
+    /// The status shown in the server list, as decided by the [StatusProvider].
+    pub fn status(&self) -> ServerStatus {
+        self.status_provider.status(self, ServerStatus::from_server(self))
+    }
+
+    pub fn set_status_provider<S: StatusProvider + 'static>(&mut self, status_provider: S) {
+        self.status_provider = Arc::new(status_provider);
+    }
+
+    pub fn channel_registry(&self) -> &ChannelRegistry {
+        &self.channel_registry
+    }
+
+    pub fn channel_registry_mut(&mut self) -> &mut ChannelRegistry {
+        &mut self.channel_registry
+    }
+
+    /// The number of ticks that have passed since the server started.
+    pub fn current_tick(&self) -> u64 {
+        self.current_tick
+    }
+
+    pub fn tick_times(&self) -> &TickTimes {
+        &self.tick_times
+    }
+
+    /// Queues a packet to be sent to the given connection at the end of the current tick.
+    pub fn send_packet<P: ClientboundPacket>(&mut self, connection: ConnectionId, packet: P) {
+        self.outbox.send_packet(connection, packet);
+    }
+
+    /// Disconnects the given connection at the end of the current tick, after all packets queued
+    /// before have been sent.
+    pub fn disconnect(&mut self, connection: ConnectionId, reason: Option<TextComponent>) {
+        self.outbox.disconnect(connection, reason);
+    }
+
+    /// Sends the player on the given connection to another server at the end of the current tick.
+    pub fn transfer(&mut self, connection: ConnectionId, host: impl Into<String>, port: u16) {
+        let packet = client::play::Transfer { host: host.into(), port: VarInt::new(port as i32) };
+        self.outbox.send_packet(connection, packet);
+    }
+
+    fn tick(&mut self) {
+        self.current_tick += 1;
+
+        self.keep_players_alive();
+    }
+
+    fn keep_players_alive(&mut self) {
+        for player in self.player_list.players_mut() {
+            match player.keep_alive_mut().tick() {
+                KeepAliveTick::Idle => {}
+                KeepAliveTick::Send(id) => {
+                    self.outbox.send_packet(player.connection(), client::play::KeepAlive { id });
+                }
+                KeepAliveTick::TimedOut => {
+                    self.outbox.disconnect(player.connection(), Some(KeepAlive::timeout_reason()));
+                }
+            }
+        }
+    }
 }
 
+#[cfg(test)]
+impl Server {
+    /// A server with the given `config.toml`, loaded from a temporary server folder.
+    pub(crate) fn for_test(config: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("keisteen-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("config.toml"), config).unwrap();
+        let server = Self::new(ServerFolder::new(path.clone()).unwrap());
+        std::fs::remove_dir_all(&path).unwrap();
+        server
+    }
+}
+
+/// Shared access to the server. A panic while the lock is held, which only ends the connection
+/// that caused it, does not make the server unusable for everyone else.
 #[derive(Clone)]
-pub struct ServerHandle(Arc<Mutex<Server>>);
+pub struct ServerHandle(Arc<RwLock<Server>>);
 
 impl ServerHandle {
     pub fn new(server: Server) -> Self {
-        Self(Arc::new(Mutex::new(server)))
+        Self(Arc::new(RwLock::new(server)))
     }
+
     pub fn read<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&Server) -> R,
     {
-        let server = self.0.lock().expect("server mutex poisoned");
+        let server = self.0.read().unwrap_or_else(PoisonError::into_inner);
         f(&server)
     }
 
@@ -75,7 +219,7 @@ impl ServerHandle {
     where
         F: FnOnce(&mut Server) -> R,
     {
-        let mut server = self.0.lock().expect("server mutex poisoned");
+        let mut server = self.0.write().unwrap_or_else(PoisonError::into_inner);
         f(&mut server)
     }
 }
@@ -1,6 +1,10 @@
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 
+use crate::server::client_settings::ClientSettings;
+use crate::server::conn::ConnectionId;
 use crate::server::entity::Entity;
+use crate::server::keep_alive::KeepAlive;
 use crate::server::player_profile::PlayerProfile;
 
 #[derive(Debug)]
@@ -8,16 +12,51 @@ pub struct Player {
     entity: Entity,
 
     profile: PlayerProfile,
+    connection: ConnectionId,
+    client_settings: ClientSettings,
+    keep_alive: KeepAlive,
 }
 
 impl Player {
-    pub fn new(profile: PlayerProfile) -> Self {
-        Self { entity: Entity::new(), profile }
+    pub(crate) fn new(
+        profile: PlayerProfile,
+        connection: ConnectionId,
+        client_settings: ClientSettings,
+        keep_alive: KeepAlive,
+    ) -> Self {
+        Self { entity: Entity::new(), profile, connection, client_settings, keep_alive }
     }
 
     pub fn profile(&self) -> &PlayerProfile {
         &self.profile
     }
+
+    pub fn connection(&self) -> ConnectionId {
+        self.connection
+    }
+
+    /// The settings of the player's client, like its locale and skin parts.
+    pub fn client_settings(&self) -> &ClientSettings {
+        &self.client_settings
+    }
+
+    /// The locale the player's client is set to, e.g. `en_us`.
+    pub fn locale(&self) -> &str {
+        &self.client_settings.locale
+    }
+
+    pub(crate) fn set_client_settings(&mut self, client_settings: ClientSettings) {
+        self.client_settings = client_settings;
+    }
+
+    /// The round-trip time of keep-alive packets, as shown in the tab list.
+    pub fn latency(&self) -> Duration {
+        self.keep_alive.latency()
+    }
+
+    pub(crate) fn keep_alive_mut(&mut self) -> &mut KeepAlive {
+        &mut self.keep_alive
+    }
 }
 
 impl Deref for Player {
@@ -1,6 +1,7 @@
 use uuid::Uuid;
 
 use crate::mc::text::text_component::TextComponent;
+use crate::server::conn::ConnectionId;
 use crate::server::player::Player;
 
 #[derive(Debug)]
@@ -19,10 +20,25 @@ impl PlayerList {
         &self.players
     }
 
+    pub(crate) fn players_mut(&mut self) -> &mut [Player] {
+        &mut self.players
+    }
+
     pub fn get_player(&self, uuid: Uuid) -> Option<&Player> {
         self.players.iter().find(|p| p.profile().uuid() == uuid)
     }
 
+    pub fn get_player_by_connection(&self, connection: ConnectionId) -> Option<&Player> {
+        self.players.iter().find(|p| p.connection() == connection)
+    }
+
+    pub(crate) fn get_player_by_connection_mut(
+        &mut self,
+        connection: ConnectionId,
+    ) -> Option<&mut Player> {
+        self.players.iter_mut().find(|p| p.connection() == connection)
+    }
+
     pub fn online_players(&self) -> i32 {
         self.players.len() as i32
     }
@@ -33,19 +49,11 @@ impl PlayerList {
 
     pub fn can_player_login(&self, uuid: Uuid) -> Result<(), TextComponent> {
         if self.get_player(uuid).is_some() {
-            return Err(TextComponent {
-                text: Some("You are already logged in.".to_string()),
-                translate: None,
-                color: None,
-            });
+            return Err(TextComponent::text("You are already logged in."));
         }
 
         if self.online_players() >= self.max_players() {
-            return Err(TextComponent {
-                text: Some("The server is full.".to_string()),
-                translate: None,
-                color: None,
-            });
+            return Err(TextComponent::text("The server is full."));
         }
 
         Ok(())
@@ -1,16 +1,27 @@
+use std::io;
+
 use uuid::Uuid;
 
+use crate::error::KeisteenResult;
+use crate::mc::protocol::packet::{
+    PrefixedProtocolRead, PrefixedProtocolWrite, ProtocolRead, ProtocolWrite,
+};
+
 #[derive(Debug, Clone)]
 pub struct PlayerProfile {
     uuid: Uuid,
     username: String,
-    // TODO: Implement player properties.
-    properties: (),
+    properties: Vec<ProfileProperty>,
 }
 
 impl PlayerProfile {
     pub fn new(uuid: Uuid, username: String) -> Self {
-        Self { uuid, username, properties: () }
+        Self { uuid, username, properties: Vec::new() }
+    }
+
+    pub fn with_properties(mut self, properties: Vec<ProfileProperty>) -> Self {
+        self.properties = properties;
+        self
     }
 
     pub fn uuid(&self) -> Uuid {
@@ -21,7 +32,64 @@ impl PlayerProfile {
         &self.username
     }
 
-    pub fn properties(&self) -> () {
-        self.properties
+    pub fn properties(&self) -> &[ProfileProperty] {
+        &self.properties
+    }
+
+    pub fn property(&self, name: &str) -> Option<&ProfileProperty> {
+        self.properties.iter().find(|property| property.name == name)
+    }
+
+    pub fn textures(&self) -> Option<&ProfileProperty> {
+        self.property(ProfileProperty::TEXTURES)
+    }
+
+    pub fn set_properties(&mut self, properties: Vec<ProfileProperty>) {
+        self.properties = properties;
+    }
+
+    pub fn set_property(&mut self, property: ProfileProperty) {
+        self.properties.retain(|p| p.name != property.name);
+        self.properties.push(property);
+    }
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ProfileProperty {
+    pub name: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl ProfileProperty {
+    pub const TEXTURES: &str = "textures";
+
+    pub fn new(
+        name: impl Into<String>,
+        value: impl Into<String>,
+        signature: Option<String>,
+    ) -> Self {
+        Self { name: name.into(), value: value.into(), signature }
+    }
+}
+
+impl ProtocolWrite for ProfileProperty {
+    fn write<W: io::Write>(&self, writer: &mut W) -> KeisteenResult<()> {
+        self.name.write(writer)?;
+        self.value.write(writer)?;
+        self.signature.write_prefixed(writer)?;
+        Ok(())
+    }
+}
+
+impl ProtocolRead for ProfileProperty {
+    fn read_from<R: io::Read>(reader: &mut R) -> KeisteenResult<Self> {
+        Ok(Self {
+            name: String::read_from(reader)?,
+            value: String::read_from(reader)?,
+            signature: Option::read_from_prefixed(reader)?,
+        })
     }
 }
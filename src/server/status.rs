@@ -0,0 +1,142 @@
+use uuid::Uuid;
+
+use crate::mc::text::legacy::LegacyFormat;
+use crate::mc::text::text_component::TextComponent;
+use crate::server::Server;
+
+/// The maximum number of players sampled in the default status.
+const MAX_PLAYER_SAMPLE: usize = 12;
+
+/// What the server list shows about the server.
+#[derive(Debug, Clone)]
+pub struct ServerStatus {
+    /// The version name, shown in place of the player counts when the client is incompatible.
+    pub version_name: String,
+    pub protocol: i32,
+    pub description: TextComponent,
+    /// The player counts and sample, or `None` to show `???` in place of the counts.
+    pub players: Option<StatusPlayers>,
+    /// The server icon as a `data:` URI.
+    pub favicon: Option<String>,
+    pub enforces_secure_chat: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusPlayers {
+    pub max: i32,
+    pub online: i32,
+    /// The entries shown when hovering over the player counts. These do not need to be real
+    /// players.
+    pub sample: Vec<StatusPlayerSample>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusPlayerSample {
+    pub name: String,
+    pub id: Uuid,
+}
+
+impl StatusPlayerSample {
+    /// A sample entry that only shows a line of text, for custom hover lists.
+    pub fn text(name: impl Into<String>) -> Self {
+        Self { name: name.into(), id: Uuid::nil() }
+    }
+}
+
+impl ServerStatus {
+    /// The status the server shows on its own, built from its properties and player list.
+    pub fn from_server(server: &Server) -> Self {
+        let config = server.server_folder().config();
+        let properties = config.properties();
+        let player_list = server.player_list();
+
+        let sample = if properties.hide_online_players {
+            Vec::new()
+        } else {
+            // Players can opt out of being listed in their client settings.
+            let players = player_list
+                .players()
+                .iter()
+                .filter(|player| player.client_settings().allow_server_listing)
+                .take(MAX_PLAYER_SAMPLE);
+            players
+                .map(|player| StatusPlayerSample {
+                    name: player.profile().username().to_string(),
+                    id: player.profile().uuid(),
+                })
+                .collect()
+        };
+
+        Self {
+            version_name: crate::MC_VERSION.to_string(),
+            protocol: crate::MC_PROTOCOL.raw(),
+            description: LegacyFormat::AMPERSAND.parse(&properties.motd),
+            players: Some(StatusPlayers {
+                max: player_list.max_players(),
+                online: player_list.online_players(),
+                sample,
+            }),
+            favicon: server.favicon().map(str::to_string),
+            enforces_secure_chat: config.enforces_secure_chat(),
+        }
+    }
+}
+
+/// Decides what the server list shows about the server, e.g. to show a maintenance banner.
+pub trait StatusProvider: Send + Sync {
+    /// Returns the status to send to a client, given the status the server would show on its
+    /// own.
+    fn status(&self, server: &Server, status: ServerStatus) -> ServerStatus;
+}
+
+/// A [StatusProvider] that shows the server's own status unchanged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultStatusProvider;
+
+impl StatusProvider for DefaultStatusProvider {
+    fn status(&self, _server: &Server, status: ServerStatus) -> ServerStatus {
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::client_settings::ClientSettings;
+    use crate::server::conn::ConnectionId;
+    use crate::server::keep_alive::KeepAlive;
+    use crate::server::player::Player;
+    use crate::server::player_profile::PlayerProfile;
+
+    fn add_player(server: &mut Server, name: &str, allow_server_listing: bool) {
+        let profile = PlayerProfile::new(Uuid::new_v4(), name.to_string());
+        let client_settings = ClientSettings { allow_server_listing, ..ClientSettings::default() };
+        let connection = ConnectionId::for_test(server.player_list().online_players() as usize);
+        let player = Player::new(profile, connection, client_settings, KeepAlive::new());
+        server.player_list_mut().add_player(player);
+    }
+
+    fn sample_names(server: &Server) -> Vec<String> {
+        let players = ServerStatus::from_server(server).players.unwrap();
+        players.sample.into_iter().map(|sample| sample.name).collect()
+    }
+
+    #[test]
+    fn leaves_out_players_who_do_not_allow_server_listing() {
+        let mut server = Server::for_test("");
+        add_player(&mut server, "Steve", true);
+        add_player(&mut server, "Alex", false);
+        add_player(&mut server, "Notch", true);
+
+        assert_eq!(sample_names(&server), ["Steve", "Notch"]);
+        assert_eq!(ServerStatus::from_server(&server).players.unwrap().online, 3);
+    }
+
+    #[test]
+    fn hides_every_player_if_configured() {
+        let mut server = Server::for_test("[properties]\nhide_online_players = true");
+        add_player(&mut server, "Steve", true);
+
+        assert!(sample_names(&server).is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::mc::protocol::packet::client::ClientboundPacket;
+use crate::mc::protocol::packet::{RawPacket, server};
+use crate::mc::text::text_component::TextComponent;
+use crate::server::ServerHandle;
+use crate::server::conn::handle::NetworkHandle;
+use crate::server::conn::{ConnectionId, catch_panic};
+use crate::server::watchdog::Watchdog;
+
+pub const TICKS_PER_SECOND: u32 = 20;
+pub const TICK_DURATION: Duration = Duration::from_millis(1000 / TICKS_PER_SECOND as u64);
+
+// When the server falls further behind than this, the missed ticks are skipped instead of being
+// caught up on.
+const MAX_CATCH_UP: Duration = Duration::from_secs(2);
+const OVERLOAD_WARNING_INTERVAL: Duration = Duration::from_secs(15);
+
+const TICK_TIME_SAMPLES: usize = 100;
+
+/// A packet received in the play state, waiting to be handled by the next tick.
+#[derive(Debug)]
+pub struct QueuedPacket {
+    pub connection: ConnectionId,
+    pub packet: RawPacket,
+}
+
+/// Runs the game at a fixed rate of [TICKS_PER_SECOND] on the current thread.
+pub struct TickLoop {
+    server: ServerHandle,
+    network: NetworkHandle,
+    packets: mpsc::Receiver<QueuedPacket>,
+    watchdog: Option<Watchdog>,
+}
+
+impl TickLoop {
+    pub fn new(
+        server: ServerHandle,
+        network: NetworkHandle,
+        packets: mpsc::Receiver<QueuedPacket>,
+    ) -> Self {
+        let max_tick_time =
+            server.read(|server| server.server_folder().config().properties().max_tick_time);
+        let watchdog = (max_tick_time > 0)
+            .then(|| Watchdog::start(Duration::from_millis(max_tick_time as u64)));
+
+        Self { server, network, packets, watchdog }
+    }
+
+    pub fn watchdog(&self) -> Option<&Watchdog> {
+        self.watchdog.as_ref()
+    }
+
+    pub fn run(mut self) {
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.watch_current_thread();
+        }
+
+        log::info!("running server at {TICKS_PER_SECOND} ticks per second");
+
+        let mut next_tick = Instant::now();
+        let mut last_overload_warning = None::<Instant>;
+        loop {
+            let now = Instant::now();
+            if now < next_tick {
+                thread::sleep(next_tick - now);
+                continue;
+            }
+
+            let behind = now - next_tick;
+            if behind > MAX_CATCH_UP {
+                if last_overload_warning
+                    .is_none_or(|last| last.elapsed() > OVERLOAD_WARNING_INTERVAL)
+                {
+                    log::warn!(
+                        "Can't keep up! Is the server overloaded? Running {}ms or {} ticks behind",
+                        behind.as_millis(),
+                        behind.as_millis() / TICK_DURATION.as_millis()
+                    );
+                    last_overload_warning = Some(now);
+                }
+                next_tick = now;
+            }
+
+            self.tick();
+            next_tick += TICK_DURATION;
+        }
+    }
+
+    fn tick(&mut self) {
+        let started_at = Instant::now();
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.tick_started();
+        }
+
+        let (outbox, favicon) = self.server.update(|server| {
+            while let Ok(QueuedPacket { connection, packet }) = self.packets.try_recv() {
+                let packet_id = packet.packet_id;
+                let result =
+                    catch_panic(|| server::play::handle_queued_packet(packet, server, connection));
+                if let Err(err) = result {
+                    log::error!("failed to handle packet {packet_id} from {connection:?}: {err}");
+                    server.disconnect(connection, None);
+                }
+            }
+
+            server.tick();
+
+            let favicon = server
+                .current_tick
+                .is_multiple_of(TICKS_PER_SECOND as u64)
+                .then(|| Arc::clone(&server.favicon));
+            (std::mem::take(&mut server.outbox), favicon)
+        });
+
+        outbox.flush(&self.network);
+
+        // The icon file is read without holding the server lock, which is only taken to swap in
+        // the new icon.
+        if let Some(favicon) = favicon.and_then(|favicon| favicon.reload_if_changed()) {
+            self.server.update(|server| server.favicon = Arc::new(favicon));
+        }
+
+        let tick_time = started_at.elapsed();
+        self.server.update(|server| server.tick_times.record(tick_time));
+
+        if let Some(watchdog) = &self.watchdog {
+            watchdog.tick_finished();
+        }
+    }
+}
+
+/// Packets sent to connections during a tick, which are handed to the network thread at the end
+/// of the tick.
+#[derive(Debug, Default)]
+pub(crate) struct Outbox {
+    messages: HashMap<ConnectionId, Vec<OutboxMessage>>,
+}
+
+#[derive(Debug)]
+enum OutboxMessage {
+    Packet(RawPacket),
+    Disconnect(Option<TextComponent>),
+}
+
+impl Outbox {
+    pub(crate) fn send_packet<P: ClientboundPacket>(
+        &mut self,
+        connection: ConnectionId,
+        packet: P,
+    ) {
+        let packet = RawPacket::from_clientbound(packet);
+        self.messages.entry(connection).or_default().push(OutboxMessage::Packet(packet));
+    }
+
+    pub(crate) fn disconnect(&mut self, connection: ConnectionId, reason: Option<TextComponent>) {
+        self.messages.entry(connection).or_default().push(OutboxMessage::Disconnect(reason));
+    }
+
+    fn flush(self, network: &NetworkHandle) {
+        for (connection, messages) in self.messages {
+            network.execute(connection, move |conn| {
+                for message in messages {
+                    match message {
+                        OutboxMessage::Packet(packet) => conn.send_raw_packet(packet)?,
+                        OutboxMessage::Disconnect(reason) => {
+                            conn.disconnect(reason);
+                            break;
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+        }
+    }
+}
+
+/// The durations of the most recent ticks.
+#[derive(Debug)]
+pub struct TickTimes {
+    samples: [Duration; TICK_TIME_SAMPLES],
+    next: usize,
+    recorded: usize,
+}
+
+impl TickTimes {
+    pub(crate) fn new() -> Self {
+        Self { samples: [Duration::ZERO; TICK_TIME_SAMPLES], next: 0, recorded: 0 }
+    }
+
+    fn record(&mut self, tick_time: Duration) {
+        self.samples[self.next] = tick_time;
+        self.next = (self.next + 1) % TICK_TIME_SAMPLES;
+        self.recorded = (self.recorded + 1).min(TICK_TIME_SAMPLES);
+    }
+
+    /// The duration of the last tick.
+    pub fn last(&self) -> Duration {
+        self.samples[(self.next + TICK_TIME_SAMPLES - 1) % TICK_TIME_SAMPLES]
+    }
+
+    /// The average tick duration in milliseconds (MSPT) over the last 100 ticks.
+    pub fn average_mspt(&self) -> f64 {
+        if self.recorded == 0 {
+            return 0.0;
+        }
+
+        let total: Duration = self.samples[..self.recorded].iter().sum();
+        total.as_secs_f64() * 1000.0 / self.recorded as f64
+    }
+}
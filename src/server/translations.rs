@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use eyre::Context;
+
+use crate::error::KeisteenResult;
+use crate::mc::text::event::HoverEvent;
+use crate::mc::text::language::{FormatSegment, Language, parse_format};
+use crate::mc::text::text_component::{Content, TextComponent};
+
+/// The directory in the server folder that contains the translation files, named after their
+/// locale, e.g. `lang/de_de.json` or `lang/de_de.toml`.
+const TRANSLATIONS_DIRECTORY: &str = "lang";
+
+/// The locale used for keys that a player's locale does not translate.
+pub const DEFAULT_LOCALE: &str = "en_us";
+
+/// Translations defined by the server, so that its own messages are shown in each player's
+/// language without a resource pack.
+///
+/// Text is only translated once the client has sent its locale in the configuration state, so the
+/// server list description is always sent as it is.
+#[derive(Debug, Default)]
+pub struct Translations {
+    languages: HashMap<String, Language>,
+}
+
+impl Translations {
+    pub(crate) fn load(server_folder_path: &Path) -> Self {
+        let mut translations = Self::default();
+
+        let path = server_folder_path.join(TRANSLATIONS_DIRECTORY);
+        if !path.is_dir() {
+            return translations;
+        }
+
+        let entries = match std::fs::read_dir(&path) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::warn!("failed to read translations directory {path:?}: {err}");
+                return translations;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let file_path = entry.path();
+            match Self::load_file(&file_path) {
+                Ok(Some((locale, language))) => {
+                    translations.languages.entry(locale).or_default().extend(language);
+                }
+                Ok(None) => {}
+                Err(err) => log::warn!("ignoring invalid translation file {file_path:?}: {err:#}"),
+            }
+        }
+
+        if !translations.languages.is_empty() {
+            log::info!("loaded translations for {} locales", translations.languages.len());
+        }
+
+        translations
+    }
+
+    /// Loads a translation file, or returns `None` if the file is not a JSON or TOML file.
+    fn load_file(path: &Path) -> KeisteenResult<Option<(String, Language)>> {
+        let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            return Ok(None);
+        };
+
+        let read = || std::fs::read_to_string(path).wrap_err("failed to read translation file");
+        let language = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Language::from_json(&read()?)?,
+            Some("toml") => Language::from_toml(&read()?)?,
+            _ => return Ok(None),
+        };
+
+        Ok(Some((locale.to_lowercase(), language)))
+    }
+
+    /// The translations for a locale, if the server has any.
+    pub fn language(&self, locale: &str) -> Option<&Language> {
+        self.languages.get(&locale.to_lowercase())
+    }
+
+    /// The format string of a key in the given locale, falling back to [DEFAULT_LOCALE].
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        self.language(locale)
+            .and_then(|language| language.get(key))
+            .or_else(|| self.languages.get(DEFAULT_LOCALE)?.get(key))
+    }
+
+    /// Replaces the translatable components whose keys the server translates with their text in
+    /// the given locale. Other keys are left for the client to translate.
+    pub fn localize(&self, component: &TextComponent, locale: &str) -> TextComponent {
+        if self.languages.is_empty() {
+            return component.clone();
+        }
+
+        let mut style = component.style.clone();
+        if let Some(HoverEvent::ShowText { value }) = &mut style.hover_event {
+            **value = self.localize(value, locale);
+        }
+
+        let mut extra: Vec<_> =
+            component.extra.iter().map(|child| self.localize(child, locale)).collect();
+
+        let content = match &component.content {
+            Content::Translatable { key, fallback, with } => {
+                let with: Vec<_> =
+                    with.iter().map(|argument| self.localize(argument, locale)).collect();
+
+                match self.get(locale, key) {
+                    Some(format) => {
+                        let segments =
+                            parse_format(format).into_iter().filter_map(|segment| match segment {
+                                FormatSegment::Text(text) => Some(TextComponent::text(text)),
+                                FormatSegment::Argument(index) => with.get(index).cloned(),
+                            });
+                        extra.splice(0..0, segments);
+                        Content::Text(String::new())
+                    }
+                    None => {
+                        Content::Translatable { key: key.clone(), fallback: fallback.clone(), with }
+                    }
+                }
+            }
+            content => content.clone(),
+        };
+
+        TextComponent { content, style, extra }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mc::text::style::Style;
+
+    const EN_US: &str = r#"{
+        "keisteen.name": "Steve",
+        "keisteen.greeting": "Hello %s!",
+        "keisteen.rules": "Be nice"
+    }"#;
+
+    const DE_DE: &str = r#"
+        [keisteen]
+        name = "Stefan"
+        greeting = "Hallo %s!"
+        said = "%2$s sagt %1$s"
+    "#;
+
+    fn translations() -> Translations {
+        let path = std::env::temp_dir().join(format!("keisteen-test-{}", uuid::Uuid::new_v4()));
+        let lang = path.join(TRANSLATIONS_DIRECTORY);
+        std::fs::create_dir_all(&lang).unwrap();
+        std::fs::write(lang.join("en_us.json"), EN_US).unwrap();
+        std::fs::write(lang.join("de_DE.toml"), DE_DE).unwrap();
+        std::fs::write(lang.join("README.txt"), "not a translation file").unwrap();
+
+        let translations = Translations::load(&path);
+        std::fs::remove_dir_all(&path).unwrap();
+        translations
+    }
+
+    /// A translated component, which the server sends as empty text followed by the parts of the
+    /// format string.
+    fn translated(parts: Vec<TextComponent>) -> TextComponent {
+        TextComponent { extra: parts, ..TextComponent::text("") }
+    }
+
+    #[test]
+    fn falls_back_to_en_us() {
+        let translations = translations();
+        assert_eq!(translations.get("de_de", "keisteen.greeting"), Some("Hallo %s!"));
+        assert_eq!(translations.get("de_de", "keisteen.rules"), Some("Be nice"));
+        assert_eq!(translations.get("fr_fr", "keisteen.greeting"), Some("Hello %s!"));
+        assert_eq!(translations.get("de_de", "keisteen.missing"), None);
+    }
+
+    #[test]
+    fn joins_nested_toml_tables_and_ignores_locale_case() {
+        let translations = translations();
+        assert_eq!(translations.get("de_DE", "keisteen.said"), Some("%2$s sagt %1$s"));
+        assert!(translations.language("de_de").is_some());
+        assert!(translations.language("readme").is_none());
+    }
+
+    #[test]
+    fn localizes_positional_arguments_recursively() {
+        let component = TextComponent::translatable(
+            "keisteen.said",
+            vec![
+                TextComponent::text("hi"),
+                TextComponent::translatable("keisteen.name", Vec::new()),
+            ],
+        );
+
+        assert_eq!(
+            translations().localize(&component, "de_de"),
+            translated(vec![
+                translated(vec![TextComponent::text("Stefan")]),
+                TextComponent::text(" sagt "),
+                TextComponent::text("hi"),
+            ])
+        );
+    }
+
+    #[test]
+    fn localizes_hover_text() {
+        let hover =
+            |value| Style { hover_event: Some(HoverEvent::show_text(value)), ..Style::default() };
+        let name = TextComponent::translatable("keisteen.name", Vec::new());
+        let component = TextComponent::text("player").with_style(hover(name));
+
+        assert_eq!(
+            translations().localize(&component, "en_us"),
+            TextComponent::text("player")
+                .with_style(hover(translated(vec![TextComponent::text("Steve")])))
+        );
+    }
+
+    #[test]
+    fn leaves_keys_the_server_does_not_define_to_the_client() {
+        let component = TextComponent::translatable(
+            "multiplayer.disconnect.kicked",
+            vec![TextComponent::translatable(
+                "keisteen.greeting",
+                vec![TextComponent::text("Alex")],
+            )],
+        );
+
+        assert_eq!(
+            translations().localize(&component, "en_us"),
+            TextComponent::translatable(
+                "multiplayer.disconnect.kicked",
+                vec![translated(vec![
+                    TextComponent::text("Hello "),
+                    TextComponent::text("Alex"),
+                    TextComponent::text("!"),
+                ])]
+            )
+        );
+    }
+}
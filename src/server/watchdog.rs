@@ -0,0 +1,158 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Aborts the process when a single tick takes longer than the configured `max_tick_time`,
+/// after dumping the stacks of the watched threads.
+#[derive(Clone)]
+pub struct Watchdog {
+    state: Arc<WatchdogState>,
+}
+
+struct WatchdogState {
+    max_tick_time: Duration,
+    tick_started_at: Mutex<Option<Instant>>,
+    threads: Mutex<Vec<WatchedThread>>,
+}
+
+impl Watchdog {
+    pub fn start(max_tick_time: Duration) -> Self {
+        let state = Arc::new(WatchdogState {
+            max_tick_time,
+            tick_started_at: Mutex::new(None),
+            threads: Mutex::new(Vec::new()),
+        });
+
+        let watched = Arc::clone(&state);
+        thread::Builder::new()
+            .name("watchdog".to_string())
+            .spawn(move || watch(&watched))
+            .expect("should spawn watchdog thread");
+
+        Self { state }
+    }
+
+    /// Includes the current thread in the stack dump written when the server hangs.
+    pub fn watch_current_thread(&self) {
+        self.state.threads.lock().expect("watchdog lock poisoned").push(WatchedThread::current());
+    }
+
+    pub(crate) fn tick_started(&self) {
+        *self.state.tick_started_at.lock().expect("watchdog lock poisoned") = Some(Instant::now());
+    }
+
+    pub(crate) fn tick_finished(&self) {
+        *self.state.tick_started_at.lock().expect("watchdog lock poisoned") = None;
+    }
+}
+
+fn watch(state: &WatchdogState) {
+    #[cfg(unix)]
+    stack_dump::install_handler();
+
+    loop {
+        thread::sleep(CHECK_INTERVAL);
+
+        let tick_started_at = *state.tick_started_at.lock().expect("watchdog lock poisoned");
+        let Some(tick_time) = tick_started_at.map(|started_at| started_at.elapsed()) else {
+            continue;
+        };
+
+        if tick_time > state.max_tick_time {
+            log::error!(
+                "A single server tick took {:.2} seconds (should be max {:.2})",
+                tick_time.as_secs_f64(),
+                state.max_tick_time.as_secs_f64()
+            );
+            log::error!("Considering it to be crashed, server will forcibly shutdown.");
+
+            for thread in state.threads.lock().expect("watchdog lock poisoned").iter() {
+                thread.dump_stack();
+            }
+
+            std::process::abort();
+        }
+    }
+}
+
+struct WatchedThread {
+    name: String,
+    #[cfg(unix)]
+    pthread: libc::pthread_t,
+}
+
+impl WatchedThread {
+    fn current() -> Self {
+        Self {
+            name: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            #[cfg(unix)]
+            pthread: stack_dump::current_pthread(),
+        }
+    }
+
+    fn dump_stack(&self) {
+        log::error!("stack of thread '{}':", self.name);
+
+        #[cfg(unix)]
+        if !stack_dump::dump(self.pthread) {
+            log::error!("thread '{}' did not respond in time", self.name);
+        }
+
+        #[cfg(not(unix))]
+        log::error!("stack dumps are not supported on this platform");
+    }
+}
+
+/// Other threads cannot be inspected from safe Rust, so the watched thread is interrupted with a
+/// signal and captures its own backtrace from within the signal handler.
+#[cfg(unix)]
+mod stack_dump {
+    use std::backtrace::Backtrace;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Duration, Instant};
+
+    const SIGNAL: libc::c_int = libc::SIGUSR1;
+    const DUMP_TIMEOUT: Duration = Duration::from_secs(1);
+
+    static DUMPED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn handle_signal(_: libc::c_int) {
+        // Capturing a backtrace is not async-signal-safe, but the process is about to be
+        // aborted anyway and the watchdog gives up after a timeout.
+        eprintln!("{}", Backtrace::force_capture());
+        DUMPED.store(true, Ordering::SeqCst);
+    }
+
+    pub(super) fn install_handler() {
+        let handler = handle_signal as extern "C" fn(libc::c_int);
+        // SAFETY: the handler has the signature expected by `signal`.
+        unsafe { libc::signal(SIGNAL, handler as libc::sighandler_t) };
+    }
+
+    pub(super) fn current_pthread() -> libc::pthread_t {
+        // SAFETY: `pthread_self` is always safe to call.
+        unsafe { libc::pthread_self() }
+    }
+
+    pub(super) fn dump(thread: libc::pthread_t) -> bool {
+        DUMPED.store(false, Ordering::SeqCst);
+
+        // SAFETY: watched threads are never joined, so the thread id stays valid while the
+        // process is running.
+        if unsafe { libc::pthread_kill(thread, SIGNAL) } != 0 {
+            return false;
+        }
+
+        let started_at = Instant::now();
+        while !DUMPED.load(Ordering::SeqCst) {
+            if started_at.elapsed() > DUMP_TIMEOUT {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        true
+    }
+}